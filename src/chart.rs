@@ -0,0 +1,1181 @@
+//! The Main tab chart for what hotplot's line chart cannot draw: percentages, the log axis, candles, panes,
+//! indicators, trigger lines and zooming. The plain line view stays on hotplot. Exported images are always drawn here.
+
+use std::{borrow::Cow, fmt::Display};
+
+use chrono::NaiveDateTime;
+use iced::{Color, HorizontalAlignment, Point, Rectangle, Size, VerticalAlignment, canvas::{self, Cursor, Frame, Geometry, Path, Stroke, Text, event::{self, Event}}, keyboard, mouse};
+
+use crate::{chart_options::{ChartKind, ChartOptions, Indicator, IndicatorKind}, indicators, styling::{ChartTheme, Theme}};
+
+const MARGIN_LEFT: f32 = 80.0;
+const MARGIN_RIGHT: f32 = 20.0;
+const MARGIN_TOP: f32 = 40.0;
+const MARGIN_BOTTOM: f32 = 30.0;
+const TITLE_SIZE: f32 = 20.0;
+const LABEL_SIZE: f32 = 14.0;
+const MIN_X_LABEL_DISTANCE: f32 = 160.0;
+const MIN_Y_LABEL_DISTANCE: f32 = 40.0;
+/// How much one step of the mouse wheel zooms in.
+const ZOOM_STEP: f32 = 0.8;
+/// How many pixels of a precise scroll make up one step of the mouse wheel.
+const PIXELS_PER_SCROLL_STEP: f32 = 50.0;
+/// The part of the height below the title and above the time labels each sub-pane takes.
+const PANE_HEIGHT_SHARE: f32 = 0.2;
+const PANE_GAP: f32 = 15.0;
+const MAX_CANDLES: i64 = 100;
+/// The candle widths to choose from, in seconds. The shortest one that keeps the number of candles
+/// under `MAX_CANDLES` is used.
+const CANDLE_INTERVALS: [i64; 9] = [
+    60 * 5,
+    60 * 15,
+    60 * 60,
+    60 * 60 * 4,
+    60 * 60 * 12,
+    60 * 60 * 24,
+    60 * 60 * 24 * 3,
+    60 * 60 * 24 * 7,
+    60 * 60 * 24 * 30,
+];
+
+/// A line on the chart, with its points ordered by time. The volumes and market caps are only
+/// drawn in their sub-panes.
+#[derive(Debug, Clone)]
+pub struct Series {
+    pub label: String,
+    pub color: Color,
+    pub points: Vec<(NaiveDateTime, f64)>,
+    pub volumes: Vec<(NaiveDateTime, f64)>,
+    pub market_caps: Vec<(NaiveDateTime, f64)>,
+    pub levels: Vec<Level>,
+    pub markers: Vec<Marker>,
+}
+
+/// A horizontal line at a price of the series, like the target of a trigger.
+#[derive(Debug, Clone)]
+pub struct Level {
+    pub price: f64,
+    pub label: String,
+    /// Whether the price is reached from below.
+    pub up: bool,
+}
+
+/// Something that happened at a price of the series, like a trigger firing.
+#[derive(Debug, Clone)]
+pub struct Marker {
+    /// In UTC, like the points of the series.
+    pub time: NaiveDateTime,
+    pub price: f64,
+    pub label: String,
+    pub up: bool,
+}
+
+/// A sub-pane under the price plot, sharing its time axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pane {
+    Volume,
+    MarketCap,
+    /// The RSI of the prices, with the given period.
+    Rsi(u32),
+}
+
+impl Display for Pane {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pane::Volume => { write!(f, "Volume") }
+            Pane::MarketCap => { write!(f, "Market cap") }
+            Pane::Rsi(period) => { write!(f, "RSI {}", period) }
+        }
+    }
+}
+
+impl Pane {
+    fn points<'a>(&self, series: &'a Series) -> Cow<'a, [(NaiveDateTime, f64)]> {
+        match self {
+            Pane::Volume => Cow::Borrowed(&series.volumes),
+            Pane::MarketCap => Cow::Borrowed(&series.market_caps),
+            Pane::Rsi(period) => Cow::Owned(indicators::rsi(&series.points, *period as usize)),
+        }
+    }
+}
+
+/// The prices of one candle interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Candle {
+    pub start: NaiveDateTime,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+}
+
+/// Groups the points into candles of `interval` seconds, counted from `origin`. Intervals without
+/// points get no candle.
+pub fn candles(points: &[(NaiveDateTime, f64)], origin: i64, interval: i64) -> Vec<Candle> {
+    let mut candles: Vec<Candle> = Vec::new();
+    for (time, price) in points.iter().cloned() {
+        let start = origin + (time.timestamp() - origin).div_euclid(interval) * interval;
+        let start = NaiveDateTime::from_timestamp(start, 0);
+        match candles.last_mut() {
+            Some(candle) if candle.start == start => {
+                candle.high = candle.high.max(price);
+                candle.low = candle.low.min(price);
+                candle.close = price;
+            }
+            _ => {
+                candles.push(Candle {
+                    start,
+                    open: price,
+                    high: price,
+                    low: price,
+                    close: price,
+                });
+            }
+        }
+    }
+    candles
+}
+
+fn candle_interval(span: i64) -> i64 {
+    CANDLE_INTERVALS.iter()
+        .cloned()
+        .find(|interval| span / interval < MAX_CANDLES)
+        .unwrap_or(CANDLE_INTERVALS[CANDLE_INTERVALS.len() - 1])
+}
+
+impl Series {
+    /// The price every other price is compared with in normalized mode: the one at the start of the period.
+    fn base(&self) -> Option<f64> {
+        self.points.first().map(|(_, price)| *price).filter(|price| *price != 0.0)
+    }
+}
+
+/// What the user did to the chart, in the times of the plot.
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// The visible range should shrink by `factor`, or grow if it is above 1, keeping `anchor` in place.
+    Zoomed { anchor: i64, factor: f32 },
+    DragStarted(Point),
+    /// The visible range should move by `by` seconds. `position` is where the drag continues from.
+    Panned { by: i64, position: Point },
+    DragEnded,
+    ModifiersChanged(keyboard::Modifiers),
+    /// A right-click, or a click with the command key held, on the price plot. `series` is the label of
+    /// the series closest to the cursor and `price` is its price at the height of the cursor.
+    AlertRequested { series: String, price: f64 },
+}
+
+/// What is drawn on a `Surface`. Lines are made of connected points.
+#[derive(Debug, Clone)]
+pub enum Shape {
+    Lines(Vec<Vec<Point>>),
+    Circle(Point, f32),
+    Rectangle(Point, Size),
+}
+
+impl Shape {
+    fn line(from: Point, to: Point) -> Self {
+        Shape::Lines(vec![vec![from, to]])
+    }
+}
+
+/// What the chart is drawn on: the canvas in the window, or an exported image.
+pub trait Surface {
+    fn width(&self) -> f32;
+    fn height(&self) -> f32;
+    fn fill_rectangle(&mut self, top_left: Point, size: Size, color: Color);
+    fn fill(&mut self, shape: &Shape, color: Color);
+    fn stroke(&mut self, shape: &Shape, stroke: Stroke);
+    fn fill_text(&mut self, text: Text);
+}
+
+impl Surface for Frame {
+    fn width(&self) -> f32 {
+        Frame::width(self)
+    }
+
+    fn height(&self) -> f32 {
+        Frame::height(self)
+    }
+
+    fn fill_rectangle(&mut self, top_left: Point, size: Size, color: Color) {
+        Frame::fill_rectangle(self, top_left, size, color);
+    }
+
+    fn fill(&mut self, shape: &Shape, color: Color) {
+        Frame::fill(self, &to_path(shape), color);
+    }
+
+    fn stroke(&mut self, shape: &Shape, stroke: Stroke) {
+        Frame::stroke(self, &to_path(shape), stroke);
+    }
+
+    fn fill_text(&mut self, text: Text) {
+        Frame::fill_text(self, text);
+    }
+}
+
+fn to_path(shape: &Shape) -> Path {
+    match shape {
+        Shape::Lines(lines) => Path::new(|builder| {
+            for line in lines.iter() {
+                if let Some((first, rest)) = line.split_first() {
+                    builder.move_to(*first);
+                    for point in rest {
+                        builder.line_to(*point);
+                    }
+                }
+            }
+        }),
+        Shape::Circle(center, radius) => Path::circle(*center, *radius),
+        Shape::Rectangle(top_left, size) => Path::rectangle(*top_left, *size),
+    }
+}
+
+/// The state of the chart that has to outlive a view. Kept by the Main tab.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChartState {
+    /// The visible time range, or None for all of the loaded data.
+    pub view: Option<(i64, i64)>,
+    /// The last cursor position of a drag that is going on.
+    pub drag: Option<Point>,
+    pub modifiers: keyboard::Modifiers,
+}
+
+/// The Main tab chart. It is built from scratch on every view, so anything that has to last
+/// longer is kept in a `ChartState`.
+pub struct Chart {
+    title: String,
+    colors: ChartTheme,
+    series: Vec<Series>,
+    normalized: bool,
+    log_scale: bool,
+    kind: ChartKind,
+    panes: Vec<Pane>,
+    /// The indicators drawn over the prices.
+    overlays: Vec<Indicator>,
+    state: ChartState,
+    legend: bool,
+}
+
+/// Where the plot ends up inside the canvas, and which values its edges stand for.
+/// On a log scale `min_y` and `max_y` are the logarithms of the values.
+struct Plot {
+    area: Rectangle,
+    min_x: i64,
+    max_x: i64,
+    min_y: f64,
+    max_y: f64,
+    log_scale: bool,
+    /// The candle width in seconds, when candles are drawn. The x-range then covers whole candles.
+    candle_interval: Option<i64>,
+}
+
+/// The price plot with the sub-panes under it.
+struct Layout {
+    price: Plot,
+    panes: Vec<(Pane, Plot)>,
+}
+
+impl Layout {
+    fn contains(&self, position: Point) -> bool {
+        self.price.area.contains(position) || self.panes.iter().any(|(_, plot)| plot.area.contains(position))
+    }
+}
+
+impl Plot {
+    fn x(&self, time: NaiveDateTime) -> f32 {
+        let span = (self.max_x - self.min_x).max(1) as f64;
+        self.area.x + ((time.timestamp() - self.min_x) as f64 / span) as f32 * self.area.width
+    }
+
+    fn y(&self, value: f64) -> f32 {
+        let span = self.max_y - self.min_y;
+        let value = if self.log_scale { value.ln() } else { value };
+        self.area.y + self.area.height - ((value - self.min_y) / span) as f32 * self.area.height
+    }
+
+    fn time(&self, x: f32) -> NaiveDateTime {
+        let span = (self.max_x - self.min_x) as f64;
+        NaiveDateTime::from_timestamp(self.min_x + (((x - self.area.x) / self.area.width) as f64 * span) as i64, 0)
+    }
+
+    /// The points within the time range of the plot, out of points ordered by time.
+    fn visible<'a>(&self, points: &'a [(NaiveDateTime, f64)]) -> &'a [(NaiveDateTime, f64)] {
+        let start = points.partition_point(|(time, _)| time.timestamp() < self.min_x);
+        let end = points.partition_point(|(time, _)| time.timestamp() <= self.max_x);
+        &points[start..end.max(start)]
+    }
+
+    fn value_at(&self, y: f32) -> f64 {
+        let value = self.min_y + ((self.area.y + self.area.height - y) / self.area.height) as f64 * (self.max_y - self.min_y);
+        if self.log_scale { value.exp() } else { value }
+    }
+
+    /// The values at the bottom and the top of the plot.
+    fn value_range(&self) -> (f64, f64) {
+        if self.log_scale {
+            (self.min_y.exp(), self.max_y.exp())
+        } else {
+            (self.min_y, self.max_y)
+        }
+    }
+}
+
+impl Chart {
+    /// In normalized mode every series is drawn relative to its first price, so series with
+    /// very different prices can be compared.
+    pub fn new(title: String, theme: Theme, series: Vec<Series>, options: &ChartOptions, state: ChartState) -> Self {
+        Self {
+            title,
+            colors: ChartTheme::from(theme),
+            series,
+            normalized: options.normalized,
+            log_scale: options.log_scale,
+            kind: options.kind,
+            panes: options.panes(),
+            overlays: options.indicators.iter().cloned().filter(|indicator| indicator.kind != IndicatorKind::Rsi).collect(),
+            state,
+            legend: false,
+        }
+    }
+
+    /// Draws the series labels on the chart itself, for images that are shown without the Main tab around them.
+    pub fn with_legend(self) -> Self {
+        Self {
+            legend: true,
+            ..self
+        }
+    }
+
+    /// The value a price is plotted at. Normalized values are ratios to the base price, so 1.0 is 0%.
+    /// Values that cannot be plotted, like zero on a log scale, are left out.
+    fn value(&self, series: &Series, price: f64) -> Option<f64> {
+        let value = if self.normalized {
+            price / series.base()?
+        } else {
+            price
+        };
+        Some(value).filter(|value| value.is_finite() && (!self.log_scale || *value > 0.0))
+    }
+
+    /// The value as shown on the y-axis: a percentage in normalized mode.
+    fn display(&self, value: f64) -> f64 {
+        if self.normalized { (value - 1.0) * 100.0 } else { value }
+    }
+
+    fn undisplay(&self, displayed: f64) -> f64 {
+        if self.normalized { displayed / 100.0 + 1.0 } else { displayed }
+    }
+
+    fn layout(&self, size: Size) -> Option<Layout> {
+        let width = size.width - MARGIN_LEFT - MARGIN_RIGHT;
+        let height = size.height - MARGIN_TOP - MARGIN_BOTTOM;
+        let pane_height = height * PANE_HEIGHT_SHARE;
+        let price_height = height - self.panes.len() as f32 * (pane_height + PANE_GAP);
+        if width <= 0.0 || price_height <= 0.0 {
+            return None;
+        }
+
+        let price = self.price_plot(Rectangle {
+            x: MARGIN_LEFT,
+            y: MARGIN_TOP,
+            width,
+            height: price_height,
+        })?;
+        let panes = self.panes.iter().enumerate().map(|(index, pane)| {
+            let area = Rectangle {
+                x: MARGIN_LEFT,
+                y: MARGIN_TOP + price_height + PANE_GAP + index as f32 * (pane_height + PANE_GAP),
+                width,
+                height: pane_height,
+            };
+            (*pane, self.pane_plot(*pane, area, &price))
+        }).collect();
+        Some(Layout {
+            price,
+            panes,
+        })
+    }
+
+    fn price_plot(&self, area: Rectangle) -> Option<Plot> {
+        let points: Vec<(i64, f64)> = self.series.iter()
+            .flat_map(|series| series.points.iter()
+                .filter_map(move |(time, price)| Some((time.timestamp(), self.value(series, *price)?))))
+            .map(|(x, y)| (x, if self.log_scale { y.ln() } else { y }))
+            .collect();
+        let (min_x, mut max_x) = match self.state.view {
+            Some(view) => view,
+            None => (points.iter().map(|(x, _)| *x).min()?, points.iter().map(|(x, _)| *x).max()?),
+        };
+        let candle_interval = match self.kind {
+            ChartKind::Line => None,
+            ChartKind::Candles => Some(candle_interval(max_x - min_x)),
+        };
+        if let Some(interval) = candle_interval {
+            max_x = min_x + ((max_x - min_x) / interval + 1) * interval;
+        }
+        let visible = || points.iter().filter(|(x, _)| *x >= min_x && *x <= max_x).map(|(_, y)| *y);
+        let mut min_y = visible().fold(f64::INFINITY, f64::min);
+        let mut max_y = visible().fold(f64::NEG_INFINITY, f64::max);
+        if !min_y.is_finite() || !max_y.is_finite() {
+            min_y = 0.0;
+            max_y = 1.0;
+        } else if min_y == max_y {
+            let padding = if min_y == 0.0 { 1.0 } else { min_y.abs() * 0.05 };
+            min_y -= padding;
+            max_y += padding;
+        }
+        let padding = (max_y - min_y) * 0.05;
+
+        Some(Plot {
+            area,
+            min_x,
+            max_x,
+            min_y: min_y - padding,
+            max_y: max_y + padding,
+            log_scale: self.log_scale,
+            candle_interval,
+        })
+    }
+
+    /// Sub-panes start at zero, except for market caps where that would flatten the line.
+    /// The RSI always goes from 0 to 100.
+    fn pane_plot(&self, pane: Pane, area: Rectangle, price: &Plot) -> Plot {
+        let values: Vec<f64> = self.series.iter()
+            .flat_map(|series| price.visible(&pane.points(series)).iter().map(|(_, value)| *value).collect::<Vec<_>>())
+            .filter(|value| value.is_finite())
+            .collect();
+        let (mut min_y, mut max_y) = match pane {
+            Pane::Volume => (0.0, values.iter().cloned().fold(f64::NEG_INFINITY, f64::max)),
+            Pane::MarketCap => (values.iter().cloned().fold(f64::INFINITY, f64::min), values.iter().cloned().fold(f64::NEG_INFINITY, f64::max)),
+            Pane::Rsi(_) => {
+                return Plot {
+                    area,
+                    min_x: price.min_x,
+                    max_x: price.max_x,
+                    min_y: 0.0,
+                    max_y: 100.0,
+                    log_scale: false,
+                    candle_interval: None,
+                };
+            }
+        };
+        if !min_y.is_finite() || !max_y.is_finite() {
+            min_y = 0.0;
+            max_y = 1.0;
+        } else if min_y == max_y {
+            let padding = if min_y == 0.0 { 1.0 } else { min_y.abs() * 0.05 };
+            min_y -= padding;
+            max_y += padding;
+        }
+        let padding = (max_y - min_y) * 0.05;
+
+        Plot {
+            area,
+            min_x: price.min_x,
+            max_x: price.max_x,
+            min_y: if pane == Pane::Volume { min_y } else { min_y - padding },
+            max_y: max_y + padding,
+            log_scale: false,
+            candle_interval: None,
+        }
+    }
+
+    fn draw_y_axis(&self, frame: &mut dyn Surface, plot: &Plot, ticks: Vec<(f64, String)>) {
+        let grid = Stroke::default().with_color(self.colors.grid).with_width(1.0);
+        let right = plot.area.x + plot.area.width;
+        for (value, label) in ticks {
+            let y = plot.y(value);
+            frame.stroke(&Shape::line(Point::new(plot.area.x, y), Point::new(right, y)), grid);
+            frame.fill_text(Text {
+                content: label,
+                position: Point::new(plot.area.x - 5.0, y),
+                color: self.colors.text,
+                size: LABEL_SIZE,
+                horizontal_alignment: HorizontalAlignment::Right,
+                vertical_alignment: VerticalAlignment::Center,
+                ..Default::default()
+            });
+        }
+    }
+
+    /// Every plot gets the same vertical grid lines, but only the lowest one is labelled.
+    fn draw_x_axis(&self, frame: &mut dyn Surface, plot: &Plot, labelled: bool) {
+        let grid = Stroke::default().with_color(self.colors.grid).with_width(1.0);
+        let bottom = plot.area.y + plot.area.height;
+        let count = ((plot.area.width / MIN_X_LABEL_DISTANCE) as i64).max(1);
+        let span = plot.max_x - plot.min_x;
+        let format = time_format(span);
+        for index in 0..=count {
+            let time = NaiveDateTime::from_timestamp(plot.min_x + span * index / count, 0);
+            let x = plot.x(time);
+            frame.stroke(&Shape::line(Point::new(x, plot.area.y), Point::new(x, bottom)), grid);
+            if !labelled {
+                continue;
+            }
+            let horizontal_alignment = match index {
+                0 => HorizontalAlignment::Left,
+                index if index == count => HorizontalAlignment::Right,
+                _ => HorizontalAlignment::Center,
+            };
+            frame.fill_text(Text {
+                content: time.format(format).to_string(),
+                position: Point::new(x, bottom + 5.0),
+                color: self.colors.text,
+                size: LABEL_SIZE,
+                horizontal_alignment,
+                vertical_alignment: VerticalAlignment::Top,
+                ..Default::default()
+            });
+        }
+    }
+
+    fn draw_series(&self, frame: &mut dyn Surface, plot: &Plot) {
+        if let Some(interval) = plot.candle_interval {
+            self.draw_candles(frame, plot, interval);
+            return;
+        }
+        for series in self.series.iter() {
+            let path = line_path(plot, &series.points, |price| self.value(series, price));
+            frame.stroke(&path, Stroke::default().with_color(series.color).with_width(2.0));
+        }
+    }
+
+    /// Levels outside of the plot are not drawn, but their labels stay at the edge they are beyond, so
+    /// they are not forgotten.
+    fn draw_levels_and_markers(&self, frame: &mut dyn Surface, plot: &Plot) {
+        let top = plot.area.y;
+        let bottom = plot.area.y + plot.area.height;
+        let right = plot.area.x + plot.area.width;
+        for series in self.series.iter() {
+            for level in series.levels.iter() {
+                let value = match self.value(series, level.price) {
+                    Some(value) => value,
+                    None => continue,
+                };
+                let color = if level.up { self.colors.up } else { self.colors.down };
+                let y = plot.y(value);
+                let (label_y, vertical_alignment) = if y < top {
+                    (top + 2.0, VerticalAlignment::Top)
+                } else if y > bottom {
+                    (bottom - 2.0, VerticalAlignment::Bottom)
+                } else {
+                    frame.stroke(&Shape::line(Point::new(plot.area.x, y), Point::new(right, y)), Stroke::default().with_color(color).with_width(1.0));
+                    (y - 2.0, VerticalAlignment::Bottom)
+                };
+                frame.fill_text(Text {
+                    content: level.label.clone(),
+                    position: Point::new(right - 5.0, label_y),
+                    color,
+                    size: LABEL_SIZE,
+                    horizontal_alignment: HorizontalAlignment::Right,
+                    vertical_alignment,
+                    ..Default::default()
+                });
+            }
+            for marker in series.markers.iter() {
+                let timestamp = marker.time.timestamp();
+                if timestamp < plot.min_x || timestamp > plot.max_x {
+                    continue;
+                }
+                let value = match self.value(series, marker.price) {
+                    Some(value) => value,
+                    None => continue,
+                };
+                let point = Point::new(plot.x(marker.time), plot.y(value));
+                let color = if marker.up { self.colors.up } else { self.colors.down };
+                let circle = Shape::Circle(point, 5.0);
+                frame.fill(&circle, color);
+                frame.stroke(&circle, Stroke::default().with_color(self.colors.text).with_width(1.0));
+                frame.fill_text(Text {
+                    content: marker.label.clone(),
+                    position: Point::new(point.x, point.y - 8.0),
+                    color: self.colors.text,
+                    size: LABEL_SIZE,
+                    horizontal_alignment: HorizontalAlignment::Center,
+                    vertical_alignment: VerticalAlignment::Bottom,
+                    ..Default::default()
+                });
+            }
+        }
+    }
+
+    /// Every indicator gets its own colour, which its name in the corner of the plot is written in.
+    fn draw_overlays(&self, frame: &mut dyn Surface, plot: &Plot) {
+        let mut legend_x = plot.area.x + 5.0;
+        for indicator in self.overlays.iter() {
+            let color = crate::styling::label_color(&indicator.to_string());
+            let stroke = Stroke::default().with_color(color).with_width(1.0);
+            let window = indicator.window as usize;
+            for series in self.series.iter() {
+                let value = |price| self.value(series, price);
+                match indicator.kind {
+                    IndicatorKind::Sma => {
+                        frame.stroke(&line_path(plot, &indicators::sma(&series.points, window), value), stroke);
+                    }
+                    IndicatorKind::Ema => {
+                        frame.stroke(&line_path(plot, &indicators::ema(&series.points, window), value), stroke);
+                    }
+                    IndicatorKind::Bollinger => {
+                        let bands = indicators::bollinger(&series.points, window, 2.0);
+                        let middle: Vec<_> = bands.iter().map(|band| (band.time, band.middle)).collect();
+                        let lower: Vec<_> = bands.iter().map(|band| (band.time, band.lower)).collect();
+                        let upper: Vec<_> = bands.iter().map(|band| (band.time, band.upper)).collect();
+                        frame.stroke(&line_path(plot, &middle, value), stroke);
+                        frame.stroke(&line_path(plot, &lower, value), Stroke { color: Color { a: 0.6, ..color }, ..stroke });
+                        frame.stroke(&line_path(plot, &upper, value), Stroke { color: Color { a: 0.6, ..color }, ..stroke });
+                    }
+                    IndicatorKind::Rsi => {}
+                }
+            }
+            let label = indicator.to_string();
+            let width = label.chars().count() as f32 * LABEL_SIZE * 0.6;
+            frame.fill_text(Text {
+                content: label,
+                position: Point::new(legend_x, plot.area.y + 2.0),
+                color,
+                size: LABEL_SIZE,
+                ..Default::default()
+            });
+            legend_x += width + 10.0;
+        }
+    }
+
+    /// Volumes are drawn as bars and the rest as lines, in the colours of their series.
+    fn draw_pane(&self, frame: &mut dyn Surface, pane: Pane, plot: &Plot) {
+        if let Pane::Rsi(_) = pane {
+            // The usual overbought and oversold levels.
+            for level in [30.0, 70.0].iter() {
+                let y = plot.y(*level);
+                frame.stroke(
+                    &Shape::line(Point::new(plot.area.x, y), Point::new(plot.area.x + plot.area.width, y)),
+                    Stroke::default().with_color(self.colors.text).with_width(1.0));
+            }
+        }
+        for series in self.series.iter() {
+            let points = pane.points(series);
+            match pane {
+                Pane::Volume => {
+                    let color = Color { a: 0.6, ..series.color };
+                    let visible = plot.visible(&points);
+                    let width = (plot.area.width / visible.len().max(1) as f32 * 0.8).max(1.0);
+                    let bottom = plot.y(0.0);
+                    for (time, volume) in visible.iter().filter(|(_, volume)| volume.is_finite()) {
+                        let top = plot.y(*volume);
+                        frame.fill_rectangle(Point::new(plot.x(*time) - width / 2.0, top), Size::new(width, bottom - top), color);
+                    }
+                }
+                Pane::MarketCap | Pane::Rsi(_) => {
+                    let path = line_path(plot, &points, |value| Some(value).filter(|value| value.is_finite()));
+                    frame.stroke(&path, Stroke::default().with_color(series.color).with_width(1.5));
+                }
+            }
+        }
+        frame.fill_text(Text {
+            content: pane.to_string(),
+            position: Point::new(plot.area.x + 5.0, plot.area.y + 2.0),
+            color: self.colors.text,
+            size: LABEL_SIZE,
+            ..Default::default()
+        });
+    }
+
+    /// The candle's values, or None if any of them cannot be plotted.
+    fn candle_values(&self, series: &Series, candle: &Candle) -> Option<[f64; 4]> {
+        Some([
+            self.value(series, candle.open)?,
+            self.value(series, candle.high)?,
+            self.value(series, candle.low)?,
+            self.value(series, candle.close)?,
+        ])
+    }
+
+    /// Bodies are coloured by direction. When several series are compared, their wicks keep the
+    /// series colours so they can be told apart.
+    fn draw_candles(&self, frame: &mut dyn Surface, plot: &Plot, interval: i64) {
+        let width = plot.area.width * interval as f32 / (plot.max_x - plot.min_x) as f32;
+        let body_width = (width * 0.7).max(1.0);
+        for series in self.series.iter() {
+            let wick_color = if self.series.len() > 1 { series.color } else { self.colors.text };
+            for candle in candles(plot.visible(&series.points), plot.min_x, interval).iter() {
+                let [open, high, low, close] = match self.candle_values(series, candle) {
+                    Some(values) => values,
+                    None => continue,
+                };
+                let x = plot.x(candle.start) + width / 2.0;
+                frame.stroke(
+                    &Shape::line(Point::new(x, plot.y(high)), Point::new(x, plot.y(low))),
+                    Stroke::default().with_color(wick_color).with_width(1.0));
+                let color = if close >= open { self.colors.up } else { self.colors.down };
+                let top = plot.y(open.max(close));
+                let height = (plot.y(open.min(close)) - top).max(1.0);
+                frame.fill_rectangle(Point::new(x - body_width / 2.0, top), Size::new(body_width, height), color);
+            }
+        }
+    }
+
+    /// Finds the candle closest to the cursor among the candles of each series under it.
+    fn hovered_candle(&self, plot: &Plot, position: Point, interval: i64) -> Option<(&Series, Candle)> {
+        let time = plot.time(position.x).timestamp();
+        let start = NaiveDateTime::from_timestamp(plot.min_x + (time - plot.min_x).div_euclid(interval) * interval, 0);
+        let mut closest: Option<(&Series, Candle, f32)> = None;
+        for series in self.series.iter() {
+            let candle = match candles(plot.visible(&series.points), plot.min_x, interval).into_iter().find(|candle| candle.start == start) {
+                Some(candle) => candle,
+                None => continue,
+            };
+            if let Some([_, high, low, _]) = self.candle_values(series, &candle) {
+                let distance = (plot.y(high) - position.y).max(position.y - plot.y(low)).max(0.0);
+                if closest.map_or(true, |(_, _, closest)| distance < closest) {
+                    closest = Some((series, candle, distance));
+                }
+            }
+        }
+        closest.map(|(series, candle, _)| (series, candle))
+    }
+
+    /// Finds the point closest to the cursor, looking only at the point of each series that is closest in time.
+    fn hovered(&self, plot: &Plot, position: Point) -> Option<(&Series, NaiveDateTime, f64)> {
+        let time = plot.time(position.x);
+        let mut closest: Option<(&Series, NaiveDateTime, f64, f32)> = None;
+        for series in self.series.iter() {
+            if let Some((time, price)) = nearest_point(plot.visible(&series.points), time) {
+                if let Some(value) = self.value(series, *price) {
+                    let distance = ((plot.x(*time) - position.x).powi(2) + (plot.y(value) - position.y).powi(2)).sqrt();
+                    if closest.map_or(true, |(_, _, _, closest)| distance < closest) {
+                        closest = Some((series, *time, *price, distance));
+                    }
+                }
+            }
+        }
+        closest.map(|(series, time, price, _)| (series, time, price))
+    }
+
+    /// The series closest to the cursor, and its price at the height of the cursor.
+    fn price_at(&self, plot: &Plot, position: Point) -> Option<(&Series, f64)> {
+        let series = self.hovered(plot, position).map(|(series, _, _)| series).or_else(|| self.series.first())?;
+        let value = plot.value_at(position.y);
+        let price = if self.normalized { value * series.base()? } else { value };
+        Some((series, price)).filter(|(_, price)| price.is_finite() && *price > 0.0)
+    }
+
+    /// The price as the tooltip shows it. In normalized mode this is the change with the price next to it.
+    fn describe_price(&self, series: &Series, price: f64) -> String {
+        match self.value(series, price) {
+            Some(value) if self.normalized => format!("{:+.2}% ({})", self.display(value), format_price(price)),
+            _ => format_price(price),
+        }
+    }
+
+    /// Snaps the crosshair to the candle under the cursor. Returns where it snapped to and the tooltip.
+    fn snap_to_candle(&self, plot: &Plot, position: Point, interval: i64) -> Option<(Point, Vec<String>)> {
+        let (series, candle) = self.hovered_candle(plot, position, interval)?;
+        let [_, _, _, close] = self.candle_values(series, &candle)?;
+        let end = NaiveDateTime::from_timestamp(candle.start.timestamp() + interval, 0);
+        let x = (plot.x(candle.start) + plot.x(end)) / 2.0;
+        let lines = vec![
+            format!("{} - {}", candle.start.format("%Y-%m-%d %H:%M"), end.format("%Y-%m-%d %H:%M")),
+            series.label.clone(),
+            format!("Open: {}", self.describe_price(series, candle.open)),
+            format!("High: {}", self.describe_price(series, candle.high)),
+            format!("Low: {}", self.describe_price(series, candle.low)),
+            format!("Close: {}", self.describe_price(series, candle.close)),
+        ];
+        Some((Point::new(x, plot.y(close)), lines))
+    }
+
+    /// Snaps the crosshair to the point closest to the cursor, and lists every series at that time.
+    fn snap_to_point(&self, frame: &mut dyn Surface, plot: &Plot, position: Point) -> Option<(Point, Vec<String>)> {
+        let (series, time, price) = self.hovered(plot, position)?;
+        let snapped = Point::new(plot.x(time), plot.y(self.value(series, price)?));
+        let mut lines = vec![time.format("%Y-%m-%d %H:%M").to_string()];
+        for series in self.series.iter() {
+            if let Some((time, price)) = nearest_point(plot.visible(&series.points), time) {
+                if let Some(value) = self.value(series, *price) {
+                    frame.fill(&Shape::Circle(Point::new(plot.x(*time), plot.y(value)), 4.0), series.color);
+                    lines.push(format!("{}: {}", series.label, self.describe_price(series, *price)));
+                }
+            }
+        }
+        Some((snapped, lines))
+    }
+
+    /// Snaps the crosshair to the time of the point closest to the cursor, and lists every series at that time.
+    fn snap_in_pane(&self, pane: Pane, plot: &Plot, position: Point) -> Option<(Point, Vec<String>)> {
+        let time = plot.time(position.x);
+        let mut snapped = None;
+        let mut lines = Vec::new();
+        for series in self.series.iter() {
+            if let Some((time, value)) = nearest_point(plot.visible(&pane.points(series)), time) {
+                if snapped.is_none() {
+                    snapped = Some(Point::new(plot.x(*time), position.y));
+                    lines.push(time.format("%Y-%m-%d %H:%M").to_string());
+                }
+                lines.push(format!("{} {}: {}", series.label, pane.to_string().to_lowercase(), format_amount(*value)));
+            }
+        }
+        Some((snapped?, lines))
+    }
+
+    /// The vertical line of the crosshair goes through every plot, so the panes can be read at the same time
+    /// as the prices. The horizontal line only goes through the plot under the cursor, and is labelled on its axis.
+    fn draw_crosshair(&self, frame: &mut dyn Surface, layout: &Layout, position: Point) {
+        let snapped = if layout.price.area.contains(position) {
+            let plot = &layout.price;
+            let snapped = match plot.candle_interval {
+                Some(interval) => self.snap_to_candle(plot, position, interval),
+                None => self.snap_to_point(frame, plot, position),
+            };
+            snapped.map(|(point, lines)| (plot, point, lines, self.display(plot.value_at(point.y)), true))
+        } else {
+            layout.panes.iter()
+                .find(|(_, plot)| plot.area.contains(position))
+                .and_then(|(pane, plot)| self.snap_in_pane(*pane, plot, position)
+                    .map(|(point, lines)| (plot, point, lines, plot.value_at(point.y), false)))
+        };
+        let (plot, point, lines, value, is_price) = match snapped {
+            Some(snapped) => snapped,
+            None => return,
+        };
+
+        let stroke = Stroke::default().with_color(Color { a: 0.5, ..self.colors.text }).with_width(1.0);
+        for area in std::iter::once(&layout.price).chain(layout.panes.iter().map(|(_, plot)| plot)).map(|plot| plot.area) {
+            frame.stroke(&Shape::line(Point::new(point.x, area.y), Point::new(point.x, area.y + area.height)), stroke);
+        }
+        frame.stroke(&Shape::line(Point::new(plot.area.x, point.y), Point::new(plot.area.x + plot.area.width, point.y)), stroke);
+
+        let label = match (is_price, self.normalized) {
+            (true, true) => format!("{:+.2}%", value),
+            (true, false) => format_price(value),
+            (false, _) => format_amount(value),
+        };
+        let size = Size::new(MARGIN_LEFT - 4.0, LABEL_SIZE + 4.0);
+        frame.fill_rectangle(Point::new(2.0, point.y - size.height / 2.0), size, self.colors.text);
+        frame.fill_text(Text {
+            content: label,
+            position: Point::new(plot.area.x - 5.0, point.y),
+            color: self.colors.background,
+            size: LABEL_SIZE,
+            horizontal_alignment: HorizontalAlignment::Right,
+            vertical_alignment: VerticalAlignment::Center,
+            ..Default::default()
+        });
+
+        self.draw_tooltip_box(frame, point, &lines, self.colors.grid);
+    }
+
+    /// Draws the lines in a box next to `point`, on whichever side has room for it.
+    fn draw_tooltip_box(&self, frame: &mut dyn Surface, point: Point, lines: &[String], border_color: Color) {
+        let padding = 5.0;
+        let width = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0) as f32 * LABEL_SIZE * 0.6 + padding * 2.0;
+        let height = lines.len() as f32 * LABEL_SIZE + padding * 2.0;
+        let mut top_left = Point::new(point.x + 10.0, point.y + 10.0);
+        if top_left.x + width > frame.width() {
+            top_left.x = point.x - 10.0 - width;
+        }
+        if top_left.y + height > frame.height() {
+            top_left.y = point.y - 10.0 - height;
+        }
+        frame.fill_rectangle(top_left, Size::new(width, height), self.colors.background);
+        frame.stroke(&Shape::Rectangle(top_left, Size::new(width, height)), Stroke::default().with_color(border_color).with_width(1.0));
+        for (index, line) in lines.iter().enumerate() {
+            frame.fill_text(Text {
+                content: line.clone(),
+                position: Point::new(top_left.x + padding, top_left.y + padding + index as f32 * LABEL_SIZE),
+                color: self.colors.text,
+                size: LABEL_SIZE,
+                ..Default::default()
+            });
+        }
+    }
+
+    /// Draws the whole chart, with the crosshair at `cursor` if there is one.
+    pub fn draw_on(&self, frame: &mut dyn Surface, cursor: Option<Point>) {
+        let size = Size::new(frame.width(), frame.height());
+        frame.fill_rectangle(Point::ORIGIN, size, self.colors.background);
+        frame.fill_text(Text {
+            content: self.title.clone(),
+            position: Point::new(size.width / 2.0, MARGIN_TOP / 2.0),
+            color: self.colors.text,
+            size: TITLE_SIZE,
+            horizontal_alignment: HorizontalAlignment::Center,
+            vertical_alignment: VerticalAlignment::Center,
+            ..Default::default()
+        });
+        if self.legend {
+            self.draw_legend(frame, size);
+        }
+        if let Some(layout) = self.layout(size) {
+            let price = &layout.price;
+            frame.fill_rectangle(price.area.position(), price.area.size(), self.colors.plot_background);
+            let count = ((price.area.height / MIN_Y_LABEL_DISTANCE) as usize).max(2);
+            self.draw_y_axis(frame, price, self.y_ticks(price, count));
+            self.draw_x_axis(frame, price, layout.panes.is_empty());
+            self.draw_series(frame, price);
+            self.draw_overlays(frame, price);
+            self.draw_levels_and_markers(frame, price);
+            for (index, (pane, plot)) in layout.panes.iter().enumerate() {
+                frame.fill_rectangle(plot.area.position(), plot.area.size(), self.colors.plot_background);
+                let count = ((plot.area.height / MIN_Y_LABEL_DISTANCE) as usize).max(2);
+                self.draw_y_axis(frame, plot, amount_ticks(plot, count));
+                self.draw_x_axis(frame, plot, index == layout.panes.len() - 1);
+                self.draw_pane(frame, *pane, plot);
+            }
+            if let Some(position) = cursor {
+                self.draw_crosshair(frame, &layout, position);
+            }
+        }
+    }
+
+    /// The series labels in their colours, right of the title. The Main tab has its own legend above the
+    /// chart, so this is only drawn on exported images.
+    fn draw_legend(&self, frame: &mut dyn Surface, size: Size) {
+        let mut x = size.width - MARGIN_RIGHT;
+        for series in self.series.iter().rev() {
+            frame.fill_text(Text {
+                content: series.label.clone(),
+                position: Point::new(x, MARGIN_TOP / 2.0),
+                color: series.color,
+                size: LABEL_SIZE,
+                horizontal_alignment: HorizontalAlignment::Right,
+                vertical_alignment: VerticalAlignment::Center,
+                ..Default::default()
+            });
+            x -= series.label.chars().count() as f32 * LABEL_SIZE * 0.6 + 15.0;
+        }
+    }
+
+    /// The values to draw grid lines at, with their labels. On a log scale these are powers of ten, and 2 and 5
+    /// times them when there is room. Ranges that are too short for that get evenly spaced labels, like
+    /// on a linear scale.
+    fn y_ticks(&self, plot: &Plot, count: usize) -> Vec<(f64, String)> {
+        let (min, max) = plot.value_range();
+        if plot.log_scale {
+            if let Some(ticks) = log_ticks(min, max, count) {
+                return ticks.into_iter().map(|tick| {
+                    let decimals = (-tick.log10().floor()).max(0.0) as usize;
+                    let label = if self.normalized {
+                        format!("{:+.*}%", decimals.saturating_sub(2), self.display(tick))
+                    } else {
+                        format!("{:.*}", decimals, tick)
+                    };
+                    (tick, label)
+                }).collect();
+            }
+        }
+
+        let low = self.display(min);
+        let high = self.display(max);
+        let step = nice_step(high - low, count);
+        let mut ticks = Vec::new();
+        let mut tick = (low / step).ceil() * step;
+        while tick <= high {
+            ticks.push((self.undisplay(tick), self.format_tick(tick, step)));
+            tick += step;
+        }
+        ticks
+    }
+
+    fn format_tick(&self, tick: f64, step: f64) -> String {
+        let decimals = (-step.log10().floor()).max(0.0) as usize;
+        // Avoids labels like "-0" for ticks that only miss zero by a rounding error.
+        let tick = if tick.abs() < step / 2.0 { 0.0 } else { tick };
+        if self.normalized {
+            format!("{:+.*}%", decimals, tick)
+        } else {
+            format!("{:.*}", decimals, tick)
+        }
+    }
+}
+
+impl canvas::Program<Message> for Chart {
+    /// Scrolling zooms, dragging pans and right-clicking asks for an alert at the price under the cursor. The time range only changes in the Main tab, which may need to load more data for it.
+    fn update(&mut self, event: Event, bounds: Rectangle, cursor: Cursor) -> (event::Status, Option<Message>) {
+        let layout = match self.layout(bounds.size()) {
+            Some(layout) => layout,
+            None => return (event::Status::Ignored, None),
+        };
+        let position = cursor.position_in(&bounds).filter(|position| layout.contains(*position));
+        match (event, position, self.state.drag) {
+            (Event::Mouse(mouse::Event::WheelScrolled { delta }), Some(position), _) => {
+                let steps = match delta {
+                    mouse::ScrollDelta::Lines { y, .. } => y,
+                    mouse::ScrollDelta::Pixels { y, .. } => y / PIXELS_PER_SCROLL_STEP,
+                };
+                let anchor = layout.price.time(position.x).timestamp();
+                (event::Status::Captured, Some(Message::Zoomed { anchor, factor: ZOOM_STEP.powf(steps) }))
+            }
+            (Event::Mouse(mouse::Event::ButtonPressed(button)), Some(position), _)
+                if layout.price.area.contains(position)
+                    && (button == mouse::Button::Right || (button == mouse::Button::Left && self.state.modifiers.is_command_pressed())) => {
+                match self.price_at(&layout.price, position) {
+                    Some((series, price)) => (event::Status::Captured, Some(Message::AlertRequested { series: series.label.clone(), price })),
+                    None => (event::Status::Ignored, None),
+                }
+            }
+            (Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)), Some(position), _) => {
+                (event::Status::Captured, Some(Message::DragStarted(position)))
+            }
+            (Event::Mouse(mouse::Event::CursorMoved { .. }), _, Some(drag)) => {
+                // The drag goes on outside of the plots, and even outside of the canvas.
+                let position = match cursor.position_from(bounds.position()) {
+                    Some(position) => position,
+                    None => return (event::Status::Ignored, None),
+                };
+                let plot = &layout.price;
+                let by = -((position.x - drag.x) / plot.area.width) as f64 * (plot.max_x - plot.min_x) as f64;
+                (event::Status::Captured, Some(Message::Panned { by: by as i64, position }))
+            }
+            (Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)), _, Some(_)) => {
+                (event::Status::Captured, Some(Message::DragEnded))
+            }
+            (Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)), _, _) => {
+                (event::Status::Ignored, Some(Message::ModifiersChanged(modifiers)))
+            }
+            _ => (event::Status::Ignored, None),
+        }
+    }
+
+    fn mouse_interaction(&self, bounds: Rectangle, cursor: Cursor) -> mouse::Interaction {
+        if self.state.drag.is_some() {
+            return mouse::Interaction::Grabbing;
+        }
+        let over_plot = cursor.position_in(&bounds)
+            .zip(self.layout(bounds.size()))
+            .map_or(false, |(position, layout)| layout.contains(position));
+        if over_plot {
+            mouse::Interaction::Crosshair
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+
+    fn draw(&self, bounds: Rectangle, cursor: Cursor) -> Vec<Geometry> {
+        let mut frame = Frame::new(bounds.size());
+        self.draw_on(&mut frame, cursor.position_in(&bounds));
+        vec![frame.into_geometry()]
+    }
+}
+
+/// Two decimals for prices above 1, and enough for cheap tokens not to show up as zero.
+pub fn format_price(price: f64) -> String {
+    if price.abs() >= 1.0 {
+        format!("{:.2}", price)
+    } else {
+        format!("{:.8}", price)
+    }
+}
+
+/// Short forms of large amounts like volumes, e.g. 1.25B.
+pub fn format_amount(amount: f64) -> String {
+    let abs = amount.abs();
+    if abs >= 1e12 {
+        format!("{:.2}T", amount / 1e12)
+    } else if abs >= 1e9 {
+        format!("{:.2}B", amount / 1e9)
+    } else if abs >= 1e6 {
+        format!("{:.2}M", amount / 1e6)
+    } else if abs >= 1e3 {
+        format!("{:.2}K", amount / 1e3)
+    } else {
+        format!("{:.2}", amount)
+    }
+}
+
+/// Evenly spaced ticks for the sub-panes, labelled in short form.
+fn amount_ticks(plot: &Plot, count: usize) -> Vec<(f64, String)> {
+    let step = nice_step(plot.max_y - plot.min_y, count);
+    let mut ticks = Vec::new();
+    let mut tick = (plot.min_y / step).ceil() * step;
+    while tick <= plot.max_y {
+        ticks.push((tick, format_amount(tick)));
+        tick += step;
+    }
+    ticks
+}
+
+/// Connects the points, leaving gaps where `value` has nothing to plot.
+fn line_path<F: Fn(f64) -> Option<f64>>(plot: &Plot, points: &[(NaiveDateTime, f64)], value: F) -> Shape {
+    let mut lines: Vec<Vec<Point>> = Vec::new();
+    let mut started = false;
+    for (time, raw) in plot.visible(points).iter() {
+        match value(*raw) {
+            Some(value) => {
+                let point = Point::new(plot.x(*time), plot.y(value));
+                match lines.last_mut() {
+                    Some(line) if started => line.push(point),
+                    _ => lines.push(vec![point]),
+                }
+                started = true;
+            }
+            None => {
+                started = false;
+            }
+        }
+    }
+    Shape::Lines(lines)
+}
+
+/// The point closest in time, assuming the points are ordered by time.
+fn nearest_point(points: &[(NaiveDateTime, f64)], time: NaiveDateTime) -> Option<&(NaiveDateTime, f64)> {
+    let index = match points.binary_search_by_key(&time, |(time, _)| *time) {
+        Ok(index) => index,
+        Err(index) => {
+            if index > 0 && (index == points.len() || time - points[index - 1].0 < points[index].0 - time) {
+                index - 1
+            } else {
+                index
+            }
+        }
+    };
+    points.get(index)
+}
+
+/// A step of 1, 2 or 5 times a power of ten that splits `range` into at most about `count` parts.
+fn nice_step(range: f64, count: usize) -> f64 {
+    let rough = range / count as f64;
+    let magnitude = 10f64.powf(rough.log10().floor());
+    let nice = match rough / magnitude {
+        residual if residual > 5.0 => 10.0,
+        residual if residual > 2.0 => 5.0,
+        residual if residual > 1.0 => 2.0,
+        _ => 1.0,
+    };
+    nice * magnitude
+}
+
+/// 1-2-5 steps between `min` and `max`, or only the powers of ten if those are too many. None if fewer than two fit.
+fn log_ticks(min: f64, max: f64, count: usize) -> Option<Vec<f64>> {
+    let first = min.log10().floor() as i32;
+    let last = max.log10().ceil() as i32;
+    for multipliers in [&[1.0, 2.0, 5.0][..], &[1.0][..]].iter() {
+        let ticks: Vec<f64> = (first..=last)
+            .flat_map(|exponent| multipliers.iter().map(move |multiplier| multiplier * 10f64.powi(exponent)))
+            .filter(|tick| *tick >= min && *tick <= max)
+            .collect();
+        if ticks.len() < 2 {
+            return None;
+        }
+        if ticks.len() <= count {
+            return Some(ticks);
+        }
+    }
+    // Even the powers of ten do not fit, so only every few of them are labelled.
+    let stride = (last - first) as usize / count + 1;
+    Some((first..=last)
+        .step_by(stride)
+        .map(|exponent| 10f64.powi(exponent))
+        .filter(|tick| *tick >= min && *tick <= max)
+        .collect())
+}
+
+fn time_format(span: i64) -> &'static str {
+    if span <= 60 * 60 * 24 * 2 {
+        "%m-%d %H:%M"
+    } else if span <= 60 * 60 * 24 * 365 * 2 {
+        "%Y-%m-%d"
+    } else {
+        "%Y-%m"
+    }
+}
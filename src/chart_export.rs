@@ -0,0 +1,227 @@
+use std::{collections::HashMap, fmt::Display, path::PathBuf, sync::Arc};
+
+use chrono::{Local, NaiveDateTime, TimeZone, Utc};
+use parquet::{column::writer::ColumnWriter, data_type::ByteArray, file::{properties::WriterProperties, writer::{FileWriter, RowGroupWriter, SerializedFileWriter}}, schema::parser::parse_message_type};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Parquet,
+}
+
+impl Display for ExportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportFormat::Csv => { write!(f, "CSV") }
+            ExportFormat::Json => { write!(f, "JSON") }
+            ExportFormat::Parquet => { write!(f, "Parquet") }
+        }
+    }
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [ExportFormat::Csv, ExportFormat::Json, ExportFormat::Parquet];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampFormat {
+    UnixSeconds,
+    UnixMillis,
+    /// RFC 3339, with the offset of the picked timezone.
+    Iso8601,
+}
+
+impl Display for TimestampFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimestampFormat::UnixSeconds => { write!(f, "Unix seconds") }
+            TimestampFormat::UnixMillis => { write!(f, "Unix milliseconds") }
+            TimestampFormat::Iso8601 => { write!(f, "ISO 8601") }
+        }
+    }
+}
+
+impl TimestampFormat {
+    pub const ALL: [TimestampFormat; 3] = [TimestampFormat::UnixSeconds, TimestampFormat::UnixMillis, TimestampFormat::Iso8601];
+}
+
+/// Unix timestamps are the same in every timezone, so this only matters for ISO 8601.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timezone {
+    Utc,
+    Local,
+}
+
+impl Display for Timezone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Timezone::Utc => { write!(f, "UTC") }
+            Timezone::Local => { write!(f, "Local time") }
+        }
+    }
+}
+
+impl Timezone {
+    pub const ALL: [Timezone; 2] = [Timezone::Utc, Timezone::Local];
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+enum Timestamp {
+    Number(i64),
+    Text(String),
+}
+
+/// One point of a series. The times of the chart data are in UTC.
+#[derive(Debug, Clone, Serialize)]
+struct ExportRow {
+    coin: String,
+    currency: String,
+    timestamp: Timestamp,
+    price: f64,
+    volume: Option<f64>,
+    market_cap: Option<f64>,
+}
+
+/// The points of one series to export, with the volumes and market caps CoinGecko sent along.
+#[derive(Debug, Clone)]
+pub struct ExportSeries {
+    pub coin: String,
+    pub currency: String,
+    pub prices: Vec<(NaiveDateTime, f64)>,
+    pub volumes: Vec<(NaiveDateTime, f64)>,
+    pub market_caps: Vec<(NaiveDateTime, f64)>,
+}
+
+fn format_timestamp(time: NaiveDateTime, format: TimestampFormat, timezone: Timezone) -> Timestamp {
+    match format {
+        TimestampFormat::UnixSeconds => Timestamp::Number(time.timestamp()),
+        TimestampFormat::UnixMillis => Timestamp::Number(time.timestamp_millis()),
+        TimestampFormat::Iso8601 => Timestamp::Text(match timezone {
+            Timezone::Utc => Utc.from_utc_datetime(&time).to_rfc3339(),
+            Timezone::Local => Local.from_utc_datetime(&time).to_rfc3339(),
+        }),
+    }
+}
+
+fn rows(series: &[ExportSeries], format: TimestampFormat, timezone: Timezone) -> Vec<ExportRow> {
+    let mut rows = Vec::new();
+    for series in series {
+        let volumes: HashMap<_, _> = series.volumes.iter().cloned().collect();
+        let market_caps: HashMap<_, _> = series.market_caps.iter().cloned().collect();
+        for (time, price) in series.prices.iter() {
+            rows.push(ExportRow {
+                coin: series.coin.clone(),
+                currency: series.currency.clone(),
+                timestamp: format_timestamp(*time, format, timezone),
+                price: *price,
+                volume: volumes.get(time).cloned(),
+                market_cap: market_caps.get(time).cloned(),
+            });
+        }
+    }
+    rows
+}
+
+/// Writes every point of the series to `path` and returns how many were written.
+pub async fn export(path: PathBuf, series: Vec<ExportSeries>, format: ExportFormat, timestamp_format: TimestampFormat, timezone: Timezone) -> Result<(PathBuf, usize), Box<dyn std::error::Error>> {
+    let rows = rows(&series, timestamp_format, timezone);
+    let count = rows.len();
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    match format {
+        ExportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            for row in rows.iter() {
+                writer.serialize(row)?;
+            }
+            tokio::fs::write(&path, writer.into_inner()?).await?;
+        }
+        ExportFormat::Json => {
+            tokio::fs::write(&path, serde_json::to_string_pretty(&rows)?).await?;
+        }
+        ExportFormat::Parquet => {
+            // The parquet writer only knows blocking files, so it runs off the async runtime.
+            let parquet_path = path.clone();
+            tokio::task::spawn_blocking(move || {
+                std::fs::File::create(&parquet_path)
+                    .map_err(From::from)
+                    .and_then(|file| write_parquet(file, &rows, timestamp_format))
+                    .map_err(|err| err.to_string())
+            }).await??;
+        }
+    }
+    Ok((path, count))
+}
+
+/// Unix timestamps are stored as numbers and ISO 8601 ones as strings. Missing volumes and market caps are nulls.
+fn write_parquet(file: std::fs::File, rows: &[ExportRow], timestamp_format: TimestampFormat) -> Result<(), Box<dyn std::error::Error>> {
+    let timestamp_type = match timestamp_format {
+        TimestampFormat::UnixSeconds | TimestampFormat::UnixMillis => "INT64",
+        TimestampFormat::Iso8601 => "BYTE_ARRAY (UTF8)",
+    };
+    let schema = parse_message_type(&format!("
+        message chart {{
+            REQUIRED BYTE_ARRAY coin (UTF8);
+            REQUIRED BYTE_ARRAY currency (UTF8);
+            REQUIRED {} timestamp;
+            REQUIRED DOUBLE price;
+            OPTIONAL DOUBLE volume;
+            OPTIONAL DOUBLE market_cap;
+        }}
+    ", timestamp_type))?;
+    let mut writer = SerializedFileWriter::new(file, Arc::new(schema), Arc::new(WriterProperties::builder().build()))?;
+    let mut row_group = writer.next_row_group()?;
+    let mut index = 0;
+    while let Some(mut column) = row_group.next_column()? {
+        match (index, &mut column) {
+            (0, ColumnWriter::ByteArrayColumnWriter(writer)) | (1, ColumnWriter::ByteArrayColumnWriter(writer)) => {
+                let values: Vec<ByteArray> = rows.iter()
+                    .map(|row| ByteArray::from(if index == 0 { row.coin.as_str() } else { row.currency.as_str() }))
+                    .collect();
+                writer.write_batch(&values, None, None)?;
+            }
+            (2, ColumnWriter::Int64ColumnWriter(writer)) => {
+                let values: Vec<i64> = rows.iter().map(|row| match row.timestamp {
+                    Timestamp::Number(value) => value,
+                    Timestamp::Text(_) => 0,
+                }).collect();
+                writer.write_batch(&values, None, None)?;
+            }
+            (2, ColumnWriter::ByteArrayColumnWriter(writer)) => {
+                let values: Vec<ByteArray> = rows.iter().map(|row| match row.timestamp {
+                    Timestamp::Number(value) => ByteArray::from(value.to_string().as_str()),
+                    Timestamp::Text(ref value) => ByteArray::from(value.as_str()),
+                }).collect();
+                writer.write_batch(&values, None, None)?;
+            }
+            (3, ColumnWriter::DoubleColumnWriter(writer)) => {
+                let values: Vec<f64> = rows.iter().map(|row| row.price).collect();
+                writer.write_batch(&values, None, None)?;
+            }
+            (4, ColumnWriter::DoubleColumnWriter(writer)) | (5, ColumnWriter::DoubleColumnWriter(writer)) => {
+                let optional: Vec<Option<f64>> = rows.iter().map(|row| if index == 4 { row.volume } else { row.market_cap }).collect();
+                let values: Vec<f64> = optional.iter().filter_map(|value| *value).collect();
+                let definition_levels: Vec<i16> = optional.iter().map(|value| value.is_some() as i16).collect();
+                writer.write_batch(&values, Some(&definition_levels), None)?;
+            }
+            _ => return Err(From::from(format!("Unexpected parquet column #{}!", index))),
+        }
+        row_group.close_column(column)?;
+        index += 1;
+    }
+    writer.close_row_group(row_group)?;
+    writer.close()?;
+    Ok(())
+}
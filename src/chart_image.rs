@@ -0,0 +1,163 @@
+//! Renders the chart to image files without a window. The chart is always drawn to SVG, and PNGs are
+//! rasterized from that SVG.
+
+use std::{fmt::{Display, Write}, path::PathBuf};
+
+use iced::{Color, HorizontalAlignment, Point, Size, VerticalAlignment, canvas::{Stroke, Text}};
+
+use crate::chart::{Chart, Shape, Surface};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Svg,
+}
+
+impl Display for ImageFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImageFormat::Png => { write!(f, "PNG") }
+            ImageFormat::Svg => { write!(f, "SVG") }
+        }
+    }
+}
+
+impl ImageFormat {
+    pub const ALL: [ImageFormat; 2] = [ImageFormat::Png, ImageFormat::Svg];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Svg => "svg",
+        }
+    }
+}
+
+struct SvgSurface {
+    width: f32,
+    height: f32,
+    body: String,
+}
+
+impl SvgSurface {
+    fn new(width: f32, height: f32) -> Self {
+        Self {
+            width,
+            height,
+            body: String::new(),
+        }
+    }
+
+    fn finish(self) -> String {
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n{2}</svg>\n",
+            self.width, self.height, self.body)
+    }
+
+    fn shape(&mut self, shape: &Shape, paint: &str) {
+        match shape {
+            Shape::Lines(lines) => {
+                for line in lines.iter() {
+                    let points: Vec<String> = line.iter().map(|point| format!("{},{}", point.x, point.y)).collect();
+                    let _ = writeln!(self.body, "<polyline points=\"{}\" {}/>", points.join(" "), paint);
+                }
+            }
+            Shape::Circle(center, radius) => {
+                let _ = writeln!(self.body, "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" {}/>", center.x, center.y, radius, paint);
+            }
+            Shape::Rectangle(top_left, size) => {
+                let _ = writeln!(self.body, "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" {}/>", top_left.x, top_left.y, size.width, size.height, paint);
+            }
+        }
+    }
+}
+
+impl Surface for SvgSurface {
+    fn width(&self) -> f32 {
+        self.width
+    }
+
+    fn height(&self) -> f32 {
+        self.height
+    }
+
+    fn fill_rectangle(&mut self, top_left: Point, size: Size, color: Color) {
+        self.fill(&Shape::Rectangle(top_left, size), color);
+    }
+
+    fn fill(&mut self, shape: &Shape, color: Color) {
+        self.shape(shape, &format!("fill=\"{}\" fill-opacity=\"{}\"", rgb(color), color.a));
+    }
+
+    fn stroke(&mut self, shape: &Shape, stroke: Stroke) {
+        self.shape(shape, &format!("fill=\"none\" stroke=\"{}\" stroke-opacity=\"{}\" stroke-width=\"{}\"", rgb(stroke.color), stroke.color.a, stroke.width));
+    }
+
+    /// SVG places text by its baseline, so the vertical alignment is approximated from the font size.
+    fn fill_text(&mut self, text: Text) {
+        let anchor = match text.horizontal_alignment {
+            HorizontalAlignment::Left => "start",
+            HorizontalAlignment::Center => "middle",
+            HorizontalAlignment::Right => "end",
+        };
+        let y = match text.vertical_alignment {
+            VerticalAlignment::Top => text.position.y + text.size * 0.8,
+            VerticalAlignment::Center => text.position.y + text.size * 0.35,
+            VerticalAlignment::Bottom => text.position.y - text.size * 0.2,
+        };
+        let _ = writeln!(
+            self.body,
+            "<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"{}\" text-anchor=\"{}\" fill=\"{}\" fill-opacity=\"{}\">{}</text>",
+            text.position.x, y, text.size, anchor, rgb(text.color), text.color.a, escape(&text.content));
+    }
+}
+
+fn rgb(color: Color) -> String {
+    let channel = |value: f32| (value.max(0.0).min(1.0) * 255.0).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", channel(color.r), channel(color.g), channel(color.b))
+}
+
+fn escape(content: &str) -> String {
+    content.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+pub fn render_svg(chart: &Chart, width: u32, height: u32) -> String {
+    let mut surface = SvgSurface::new(width as f32, height as f32);
+    chart.draw_on(&mut surface, None);
+    surface.finish()
+}
+
+/// Text is drawn with the fonts installed on the system.
+pub fn render_png(chart: &Chart, width: u32, height: u32) -> Result<image::RgbaImage, Box<dyn std::error::Error>> {
+    let mut options = usvg::Options::default();
+    options.fontdb.load_system_fonts();
+    let tree = usvg::Tree::from_str(&render_svg(chart, width, height), &options)?;
+    let mut pixmap = tiny_skia::Pixmap::new(width, height)
+        .ok_or::<Box<dyn std::error::Error>>(From::from("The image has to be at least 1x1!"))?;
+    resvg::render(&tree, usvg::FitTo::Original, pixmap.as_mut())
+        .ok_or::<Box<dyn std::error::Error>>(From::from("Failed to render the chart!"))?;
+    // The background is opaque, so the premultiplied pixels are the same as straight ones.
+    image::RgbaImage::from_raw(width, height, pixmap.data().to_vec())
+        .ok_or::<Box<dyn std::error::Error>>(From::from("Failed to render the chart!"))
+}
+
+pub async fn export(path: PathBuf, chart: Chart, format: ImageFormat, width: u32, height: u32) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    match format {
+        ImageFormat::Svg => {
+            tokio::fs::write(&path, render_svg(&chart, width, height)).await?;
+        }
+        ImageFormat::Png => {
+            // Loading the fonts, rasterizing and encoding all block, so they run off the async runtime.
+            let png_path = path.clone();
+            tokio::task::spawn_blocking(move || {
+                render_png(&chart, width, height)
+                    .and_then(|png| Ok(png.save_with_format(&png_path, image::ImageFormat::Png)?))
+                    .map_err(|err| err.to_string())
+            }).await??;
+        }
+    }
+    Ok(path)
+}
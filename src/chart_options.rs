@@ -0,0 +1,243 @@
+use std::{fmt::Display, time::Duration};
+
+use chrono::{NaiveDate, NaiveDateTime};
+
+use crate::chart::Pane;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimePeriod {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+    All,
+    Custom
+}
+
+impl Default for TimePeriod {
+    fn default() -> Self {
+        Self::Weekly
+    }
+}
+
+impl Display for TimePeriod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimePeriod::Daily => { write!(f, "Daily") }
+            TimePeriod::Weekly => { write!(f, "Weekly") }
+            TimePeriod::Monthly => { write!(f, "Monthly") }
+            TimePeriod::Yearly => { write!(f, "Yearly") }
+            TimePeriod::All => { write!(f, "All") }
+            TimePeriod::Custom => { write!(f, "Custom") }
+        }
+    }
+}
+
+impl TimePeriod {
+    pub fn all() -> Vec<Self> {
+        vec![Self::Daily, Self::Weekly, Self::Monthly, Self::Yearly, Self::All, Self::Custom]
+    }
+
+    pub fn from_discriminant(discriminant: u8) -> Option<Self> {
+        Self::all().get(discriminant as usize).cloned()
+    }
+
+    /// How often the chart is refreshed when auto-refresh is on. Custom periods are in the past and are not refreshed.
+    pub fn refresh_interval(&self) -> Option<Duration> {
+        match self {
+            TimePeriod::Daily => Some(Duration::from_secs(30)),
+            TimePeriod::Weekly => Some(Duration::from_secs(60 * 5)),
+            TimePeriod::Monthly => Some(Duration::from_secs(60 * 30)),
+            TimePeriod::Yearly | TimePeriod::All => Some(Duration::from_secs(60 * 60)),
+            TimePeriod::Custom => None,
+        }
+    }
+
+    pub fn get_from_to(&self, current: u64, default_from: u64, default_to: u64) -> (u64, u64) {
+        return match self {
+            TimePeriod::Daily => {
+                let to = current - (current % 60);
+                let from = to - 60*60*24;
+                (from, to)
+            }
+            TimePeriod::Weekly => {
+                let to = current - (current % (60*60));
+                let from = to - 60*60*24*7;
+                (from, to)
+            }
+            TimePeriod::Monthly => {
+                let to = current - (current % (60*60*24));
+                let from = to - 60*60*24*30;
+                (from, to)
+            }
+            TimePeriod::Yearly => {
+                let to = current - (current % (60*60*24));
+                let from = to - 60*60*24*365;
+                (from, to)
+            }
+            TimePeriod::All => {
+                let to = current - (current % (60*60*24));
+                let from = 0;
+                (from, to)
+            }
+            TimePeriod::Custom => {
+                (default_from, default_to)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChartKind {
+    Line,
+    /// Candles are built from the loaded prices, so they need no extra requests.
+    Candles,
+}
+
+impl Default for ChartKind {
+    fn default() -> Self {
+        Self::Line
+    }
+}
+
+impl Display for ChartKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChartKind::Line => { write!(f, "Line") }
+            ChartKind::Candles => { write!(f, "Candles") }
+        }
+    }
+}
+
+impl ChartKind {
+    pub const ALL: [ChartKind; 2] = [ChartKind::Line, ChartKind::Candles];
+
+    pub fn from_discriminant(discriminant: u8) -> Option<Self> {
+        Self::ALL.get(discriminant as usize).cloned()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndicatorKind {
+    Sma,
+    Ema,
+    Bollinger,
+    Rsi,
+}
+
+impl Display for IndicatorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IndicatorKind::Sma => { write!(f, "SMA") }
+            IndicatorKind::Ema => { write!(f, "EMA") }
+            IndicatorKind::Bollinger => { write!(f, "Bollinger") }
+            IndicatorKind::Rsi => { write!(f, "RSI") }
+        }
+    }
+}
+
+impl IndicatorKind {
+    pub const ALL: [IndicatorKind; 4] = [IndicatorKind::Sma, IndicatorKind::Ema, IndicatorKind::Bollinger, IndicatorKind::Rsi];
+
+    pub fn from_discriminant(discriminant: u8) -> Option<Self> {
+        Self::ALL.get(discriminant as usize).cloned()
+    }
+
+    /// The window most charting tools start out with.
+    pub fn default_window(&self) -> u32 {
+        match self {
+            IndicatorKind::Sma | IndicatorKind::Ema | IndicatorKind::Bollinger => 20,
+            IndicatorKind::Rsi => 14,
+        }
+    }
+}
+
+/// An indicator on the Main tab chart. The window is a number of loaded points, not a length of time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Indicator {
+    pub kind: IndicatorKind,
+    pub window: u32,
+}
+
+impl Display for Indicator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.kind, self.window)
+    }
+}
+
+/// A coin/currency pair on the Main tab chart. Coins are referenced by their CoinGecko id and
+/// currencies by name, like in exported triggers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SeriesKey {
+    pub coin: String,
+    pub currency: String,
+}
+
+impl Display for SeriesKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} / {}", self.coin, self.currency)
+    }
+}
+
+/// How the Main tab chart is set up. Persisted in the settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartOptions {
+    /// Never empty. The first series is drawn in the graph colour from the settings.
+    pub series: Vec<SeriesKey>,
+    pub time_period: TimePeriod,
+    /// The range of the Custom period, in UTC.
+    pub date_from: NaiveDateTime,
+    pub date_to: NaiveDateTime,
+    /// Whether every series is shown as its change since the start of the period rather than its price.
+    pub normalized: bool,
+    pub log_scale: bool,
+    pub kind: ChartKind,
+    pub show_volume: bool,
+    pub show_market_cap: bool,
+    /// Every RSI gets a sub-pane, the other indicators are drawn over the prices.
+    pub indicators: Vec<Indicator>,
+    pub auto_refresh: bool,
+    /// Whether the targets of active triggers and the firings from the history are drawn on the chart.
+    pub show_triggers: bool,
+}
+
+impl ChartOptions {
+    /// The sub-panes to draw under the price plot, from top to bottom.
+    pub fn panes(&self) -> Vec<Pane> {
+        let mut panes = Vec::new();
+        if self.show_volume {
+            panes.push(Pane::Volume);
+        }
+        if self.show_market_cap {
+            panes.push(Pane::MarketCap);
+        }
+        for indicator in self.indicators.iter() {
+            if indicator.kind == IndicatorKind::Rsi {
+                panes.push(Pane::Rsi(indicator.window));
+            }
+        }
+        panes
+    }
+}
+
+impl Default for ChartOptions {
+    fn default() -> Self {
+        Self {
+            series: vec![SeriesKey {
+                coin: "bitcoin".to_string(),
+                currency: "usd".to_string(),
+            }],
+            time_period: Default::default(),
+            date_from: NaiveDate::from_ymd(2017, 1, 1).and_hms(0, 0, 0),
+            date_to: NaiveDate::from_ymd(2018, 1, 1).and_hms(0, 0, 0),
+            normalized: false,
+            log_scale: false,
+            kind: Default::default(),
+            show_volume: false,
+            show_market_cap: false,
+            indicators: Vec::new(),
+            auto_refresh: false,
+            show_triggers: false,
+        }
+    }
+}
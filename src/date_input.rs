@@ -0,0 +1,124 @@
+//! Dates typed into the date-range picker. Times are in UTC, like the chart data.
+
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+const DATE_TIME_FORMATS: [&str; 4] = ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"];
+
+/// How a date is shown in the picker, so that it can be typed back in.
+pub const DISPLAY_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// Accepts ISO 8601 dates with an optional time of day, "now", and times relative to now like
+/// "-90d" or "-12h". The units are min, h, d, w, mo (30 days) and y (365 days).
+/// A date without a time of day stands for the start of that day.
+pub fn parse(input: &str, now: NaiveDateTime) -> Result<NaiveDateTime, String> {
+    parse_bound(input, now, false)
+}
+
+/// Like `parse`, but a date without a time of day stands for the end of that day, so that a range
+/// typed as 2021-03-01 to 2021-03-31 includes the 31st, just like when the days are picked on the calendar.
+pub fn parse_end(input: &str, now: NaiveDateTime) -> Result<NaiveDateTime, String> {
+    parse_bound(input, now, true)
+}
+
+fn parse_bound(input: &str, now: NaiveDateTime, end: bool) -> Result<NaiveDateTime, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(format!("Enter a date"));
+    }
+    if input.eq_ignore_ascii_case("now") {
+        return Ok(now);
+    }
+    if input.starts_with('-') || input.starts_with('+') {
+        let offset = parse_relative(input)?;
+        return now.checked_add_signed(offset).ok_or_else(|| format!("'{}' is too far away", input));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        let start = date.and_hms(0, 0, 0);
+        return Ok(if end { start + Duration::days(1) } else { start });
+    }
+    DATE_TIME_FORMATS.iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(input, format).ok())
+        .ok_or_else(|| format!("'{}' is not a date like 2021-03-31, 2021-03-31 12:00 or -90d", input))
+}
+
+fn parse_relative(input: &str) -> Result<Duration, String> {
+    let (sign, rest) = input.split_at(1);
+    let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+    let (amount, unit) = rest.split_at(digits);
+    let amount = amount.parse::<i64>().map_err(|_| format!("'{}' has no number of units", input))?;
+    let unit_seconds = match unit.trim() {
+        "min" => 60,
+        "h" => 60 * 60,
+        "d" => 60 * 60 * 24,
+        "w" => 60 * 60 * 24 * 7,
+        "mo" => 60 * 60 * 24 * 30,
+        "y" => 60 * 60 * 24 * 365,
+        unit => return Err(format!("'{}' is not a unit, use min, h, d, w, mo or y", unit)),
+    };
+    let seconds = amount.checked_mul(unit_seconds).filter(|seconds| *seconds <= i64::MAX / 1000)
+        .ok_or_else(|| format!("'{}' is too far away", input))?;
+    Ok(Duration::seconds(if sign == "-" { -seconds } else { seconds }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(date: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn dates_and_times() {
+        let now = at("2021-06-15 12:30:00");
+        assert_eq!(parse("2021-03-31", now), Ok(at("2021-03-31 00:00:00")));
+        assert_eq!(parse("2021-03-31 12:00", now), Ok(at("2021-03-31 12:00:00")));
+        assert_eq!(parse("2021-03-31T12:00:30", now), Ok(at("2021-03-31 12:00:30")));
+        assert_eq!(parse("  now ", now), Ok(now));
+        assert_eq!(parse("NOW", now), Ok(now));
+    }
+
+    #[test]
+    fn an_end_day_includes_the_whole_day() {
+        let now = at("2021-06-15 12:30:00");
+        assert_eq!(parse_end("2021-03-31", now), Ok(at("2021-04-01 00:00:00")));
+        assert_eq!(parse_end("2021-12-31", now), Ok(at("2022-01-01 00:00:00")));
+        // A time of day, "now" and relative times are taken as they are.
+        assert_eq!(parse_end("2021-03-31 12:00", now), Ok(at("2021-03-31 12:00:00")));
+        assert_eq!(parse_end("now", now), Ok(now));
+        assert_eq!(parse_end("-1d", now), Ok(at("2021-06-14 12:30:00")));
+    }
+
+    #[test]
+    fn shown_dates_can_be_typed_back_in() {
+        let date = at("2021-03-31 17:45:00");
+        let shown = date.format(DISPLAY_FORMAT).to_string();
+        assert_eq!(parse(&shown, date), Ok(date));
+        assert_eq!(parse_end(&shown, date), Ok(date));
+    }
+
+    #[test]
+    fn relative_times() {
+        let now = at("2021-06-15 12:30:00");
+        assert_eq!(parse("-90d", now), Ok(at("2021-03-17 12:30:00")));
+        assert_eq!(parse("-12h", now), Ok(at("2021-06-15 00:30:00")));
+        assert_eq!(parse("-30min", now), Ok(at("2021-06-15 12:00:00")));
+        assert_eq!(parse("-2w", now), Ok(at("2021-06-01 12:30:00")));
+        assert_eq!(parse("-1mo", now), Ok(at("2021-05-16 12:30:00")));
+        assert_eq!(parse("-1y", now), Ok(at("2020-06-15 12:30:00")));
+        assert_eq!(parse("+1h", now), Ok(at("2021-06-15 13:30:00")));
+    }
+
+    #[test]
+    fn invalid_input() {
+        let now = at("2021-06-15 12:30:00");
+        assert!(parse("", now).is_err());
+        assert!(parse("yesterday", now).is_err());
+        assert!(parse("2021-02-30", now).is_err());
+        assert!(parse("-d", now).is_err());
+        assert!(parse("-5s", now).is_err());
+        assert!(parse_end("2021-13-01", now).is_err());
+        // More seconds than a date can be away from now.
+        assert!(parse("-99999999999y", now).is_err());
+    }
+}
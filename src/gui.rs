@@ -1,19 +1,25 @@
 use std::{path::PathBuf, rc::Rc, sync::{Arc, RwLock}};
 
-use iced::{Align, Button, Clipboard, Color, Column, Command, Container, Element, Length, Row, Text, button};
+use iced::{Align, Button, Clipboard, Color, Column, Command, Container, Element, Length, Row, Subscription, Text, button};
 
 use crate::*;
+use crate::notification_center::{NotificationCenter, NotificationTarget};
 
 pub struct Flags {
     pub settings: Arc<RwLock<crate::settings::Settings>>,
     pub coins: Rc<Vec<coingecko_requests::data::Coin>>,
     pub currencies: Rc<Vec<coingecko_requests::data::VsCurrency>>,
+    pub notifications: Arc<RwLock<NotificationCenter>>,
+    pub prices: Arc<RwLock<trigger_engine::PriceSnapshot>>,
+    pub triggers_file_status: Arc<RwLock<triggers_file::TriggersFileStatus>>,
+    pub engine_updates: tokio::sync::broadcast::Sender<()>,
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum Tab {
     Main,
     Triggers,
+    Notifications,
     Settings,
     About,
 }
@@ -22,13 +28,19 @@ pub struct Gui {
     settings: Arc<RwLock<crate::settings::Settings>>,
     coins: Rc<Vec<coingecko_requests::data::Coin>>,
     currencies: Rc<Vec<coingecko_requests::data::VsCurrency>>,
+    notifications: Arc<RwLock<NotificationCenter>>,
+    prices: Arc<RwLock<trigger_engine::PriceSnapshot>>,
+    triggers_file_status: Arc<RwLock<triggers_file::TriggersFileStatus>>,
+    engine_updates: tokio::sync::broadcast::Sender<()>,
     active_tab: Tab,
     main_button_state: button::State,
     triggers_button_state: button::State,
+    notifications_button_state: button::State,
     settings_button_state: button::State,
     about_button_state: button::State,
     main_tab_state: Option<main_tab_gui::Gui>,
     triggers_tab_state: Option<triggers_tab_gui::Gui>,
+    notifications_tab_state: Option<notifications_tab_gui::Gui>,
     settings_tab_state: Option<settings_tab_gui::Gui>,
     about_tab_state: Option<about_tab_gui::Gui>,
 }
@@ -38,6 +50,8 @@ pub enum Message {
     TabSelected(Tab),
     MainTabMessage(main_tab_gui::Message),
     TriggersTabMessage(triggers_tab_gui::Message),
+    NotificationsTabMessage(notifications_tab_gui::Message),
+    EngineUpdated,
     SettingsTabMessage(settings_tab_gui::Message),
     AboutTabMessage(about_tab_gui::Message),
 }
@@ -48,18 +62,25 @@ impl Gui {
             coins: flags.coins.clone(),
             currencies: flags.currencies.clone(),
             settings: flags.settings.clone(),
+            notifications: flags.notifications.clone(),
         });
         (Self {
             settings: flags.settings,
             coins: flags.coins,
             currencies: flags.currencies,
+            notifications: flags.notifications,
+            prices: flags.prices,
+            triggers_file_status: flags.triggers_file_status,
+            engine_updates: flags.engine_updates,
             active_tab: Tab::Main,
             main_button_state: Default::default(),
             triggers_button_state: Default::default(),
+            notifications_button_state: Default::default(),
             settings_button_state: Default::default(),
             about_button_state: Default::default(),
             main_tab_state: Some(main_tab_state),
             triggers_tab_state: None,
+            notifications_tab_state: None,
             settings_tab_state: None,
             about_tab_state: None,
         }, main_tab_init_message.map(Message::MainTabMessage))
@@ -68,64 +89,22 @@ impl Gui {
     pub fn update(&mut self, message: Message, clipboard: &mut Clipboard) -> Command<Message> {
         match message {
             Message::TabSelected(tab) => {
-                self.active_tab = tab;
-                match tab {
-                    Tab::Main => {
-                        let _ = self.main_tab_state.as_ref().expect("SHOULD NOT HAPPEN: main tab is initilized in the `new` function!");
-                        Command::none()
-                    }
-                    Tab::Triggers => {
-                        match self.triggers_tab_state {
-                            Some(_) => {
-                                Command::none()
-                            }
-                            None => {
-                                let (triggers_tab_state, triggers_tab_init_message) = triggers_tab_gui::Gui::new(triggers_tab_gui::Flags {
-                                    coins: self.coins.clone(),
-                                    currencies: self.currencies.clone(),
-                                    settings: self.settings.clone(),
-                                });
-                                self.triggers_tab_state = Some(triggers_tab_state);
-                                triggers_tab_init_message.map(Message::TriggersTabMessage)
-                            }
-                        }
-                    }
-                    Tab::Settings => {
-                        match self.settings_tab_state {
-                            Some(_) => {
-                                Command::none()
-                            }
-                            None => {
-                                let (settings_tab_state, settings_tab_init_message) = settings_tab_gui::Gui::new(settings_tab_gui::Flags {
-                                    settings: self.settings.clone(),
-                                });
-                                self.settings_tab_state = Some(settings_tab_state);
-                                settings_tab_init_message.map(Message::SettingsTabMessage)
-                            }
-                        }
-                    }
-                    Tab::About => {
-                        match self.about_tab_state {
-                            Some(_) => {
-                                Command::none()
-                            }
-                            None => {
-                                let (about_tab_state, about_tab_init_message) = about_tab_gui::Gui::new(about_tab_gui::Flags {
-                                    settings: self.settings.clone(),
-                                });
-                                self.about_tab_state = Some(about_tab_state);
-                                about_tab_init_message.map(Message::AboutTabMessage)
-                            }
-                        }
-                    }
-                }
+                self.select_tab(tab)
             }
             Message::MainTabMessage(msg) => {
-                self.main_tab_state
+                // A trigger set on the chart has to show up in the triggers tab too.
+                let trigger_added = matches!(msg, main_tab_gui::Message::AlertCreated(Ok(_)));
+                let command = self.main_tab_state
                     .as_mut()
                     .expect("SHOULD NOT HAPPEN: main tab is initilized in the `new` function!")
                     .update(msg, clipboard)
-                    .map(Message::MainTabMessage)
+                    .map(Message::MainTabMessage);
+                if trigger_added && self.triggers_tab_state.is_some() {
+                    let reload = triggers_tab_gui::reload_triggers().map(Message::TriggersTabMessage);
+                    Command::batch(vec![command, reload])
+                } else {
+                    command
+                }
             }
             Message::TriggersTabMessage(msg) => {
                 self.triggers_tab_state
@@ -134,6 +113,26 @@ impl Gui {
                     .update(msg, clipboard)
                     .map(Message::TriggersTabMessage)
             }
+            Message::NotificationsTabMessage(msg) => {
+                let target = match msg {
+                    notifications_tab_gui::Message::OpenClicked(id) => {
+                        self.notifications.read().unwrap().get(id).and_then(|entry| entry.target.clone())
+                    }
+                    _ => None
+                };
+                let command = self.notifications_tab_state
+                    .as_mut()
+                    .expect("SHOULD NOT HAPPEN: a message for notifications tab cannot be received before it is initialized!")
+                    .update(msg, clipboard)
+                    .map(Message::NotificationsTabMessage);
+                match target {
+                    Some(target) => Command::batch(vec![command, self.open_target(target)]),
+                    None => command
+                }
+            }
+            Message::EngineUpdated => {
+                Command::none()
+            }
             Message::SettingsTabMessage(msg) => {
                 self.settings_tab_state
                     .as_mut()
@@ -151,10 +150,115 @@ impl Gui {
         }
     }
 
+    fn select_tab(&mut self, tab: Tab) -> Command<Message> {
+        self.active_tab = tab;
+        match tab {
+            Tab::Main => {
+                let _ = self.main_tab_state.as_ref().expect("SHOULD NOT HAPPEN: main tab is initilized in the `new` function!");
+                Command::none()
+            }
+            Tab::Triggers => {
+                match self.triggers_tab_state {
+                    Some(_) => {
+                        Command::none()
+                    }
+                    None => {
+                        let (triggers_tab_state, triggers_tab_init_message) = triggers_tab_gui::Gui::new(triggers_tab_gui::Flags {
+                            coins: self.coins.clone(),
+                            currencies: self.currencies.clone(),
+                            settings: self.settings.clone(),
+                            prices: self.prices.clone(),
+                            triggers_file_status: self.triggers_file_status.clone(),
+                        });
+                        self.triggers_tab_state = Some(triggers_tab_state);
+                        triggers_tab_init_message.map(Message::TriggersTabMessage)
+                    }
+                }
+            }
+            Tab::Notifications => {
+                match self.notifications_tab_state {
+                    Some(_) => {
+                        Command::none()
+                    }
+                    None => {
+                        let (notifications_tab_state, notifications_tab_init_message) = notifications_tab_gui::Gui::new(notifications_tab_gui::Flags {
+                            settings: self.settings.clone(),
+                            notifications: self.notifications.clone(),
+                        });
+                        self.notifications_tab_state = Some(notifications_tab_state);
+                        notifications_tab_init_message.map(Message::NotificationsTabMessage)
+                    }
+                }
+            }
+            Tab::Settings => {
+                match self.settings_tab_state {
+                    Some(_) => {
+                        Command::none()
+                    }
+                    None => {
+                        let (settings_tab_state, settings_tab_init_message) = settings_tab_gui::Gui::new(settings_tab_gui::Flags {
+                            settings: self.settings.clone(),
+                        });
+                        self.settings_tab_state = Some(settings_tab_state);
+                        settings_tab_init_message.map(Message::SettingsTabMessage)
+                    }
+                }
+            }
+            Tab::About => {
+                match self.about_tab_state {
+                    Some(_) => {
+                        Command::none()
+                    }
+                    None => {
+                        let (about_tab_state, about_tab_init_message) = about_tab_gui::Gui::new(about_tab_gui::Flags {
+                            settings: self.settings.clone(),
+                        });
+                        self.about_tab_state = Some(about_tab_state);
+                        about_tab_init_message.map(Message::AboutTabMessage)
+                    }
+                }
+            }
+        }
+    }
+
+    fn open_target(&mut self, target: NotificationTarget) -> Command<Message> {
+        match target {
+            NotificationTarget::Chart { coin, currency } => {
+                let command = self.select_tab(Tab::Main);
+                let main_command = self.main_tab_state
+                    .as_mut()
+                    .expect("SHOULD NOT HAPPEN: main tab is initilized in the `new` function!")
+                    .show_pair(&coin, &currency)
+                    .map(Message::MainTabMessage);
+                Command::batch(vec![command, main_command])
+            }
+            NotificationTarget::Trigger(rowid) => {
+                let command = self.select_tab(Tab::Triggers);
+                self.triggers_tab_state
+                    .as_mut()
+                    .expect("SHOULD NOT HAPPEN: triggers tab is initialized when it is selected!")
+                    .focus_trigger(rowid);
+                command
+            }
+        }
+    }
+
+    /// The unread counter and the trigger prices change from the trigger engine's thread,
+    /// so the view is refreshed whenever the engine says it finished a check.
+    pub fn subscription(&self) -> Subscription<Message> {
+        let mut subscriptions = vec![trigger_engine::updates(&self.engine_updates).map(|_| Message::EngineUpdated)];
+        if let Some(ref main_tab_state) = self.main_tab_state {
+            subscriptions.push(main_tab_state.subscription().map(Message::MainTabMessage));
+        }
+        Subscription::batch(subscriptions)
+    }
+
     pub fn view(&mut self) -> iced::Element<'_, Message> {
         let lock = self.settings.write().unwrap();
         let theme = lock.theme.clone();
         drop(lock);
+        let unread_count = self.notifications.read().unwrap().unread_count();
+        let notifications_label = if unread_count == 0 { format!("Alerts") } else { format!("Alerts ({})", unread_count) };
 
         let mut global_menu = Column::new()
             .spacing(5)
@@ -172,6 +276,11 @@ impl Gui {
                 .on_press(Message::TabSelected(Tab::Triggers))
                 .width(Length::Units(100))
                 .style(theme));
+        global_menu = global_menu.push(
+            Button::new(&mut self.notifications_button_state, Text::new(notifications_label))
+                .on_press(Message::TabSelected(Tab::Notifications))
+                .width(Length::Units(100))
+                .style(theme));
         global_menu = global_menu.push(
             Button::new(&mut self.settings_button_state, Text::new("Settings".to_string()))
                 .on_press(Message::TabSelected(Tab::Settings))
@@ -206,6 +315,13 @@ impl Gui {
                     .view()
                     .map(Message::TriggersTabMessage));
             }
+            Tab::Notifications => {
+                element = element.push(self.notifications_tab_state
+                    .as_mut()
+                    .expect("SHOULD NOT HAPPEN: notifications tab cannot be selected before it is initialized!")
+                    .view()
+                    .map(Message::NotificationsTabMessage));
+            }
             Tab::Settings => {
                 element = element.push(self.settings_tab_state
                     .as_mut()
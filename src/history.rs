@@ -0,0 +1,74 @@
+use std::path::PathBuf;
+
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use tokio::{fs::OpenOptions, io::AsyncWriteExt};
+
+use crate::trigger_engine::Firing;
+
+/// A single trigger firing, stored as one JSON line in the history file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Unix seconds.
+    pub fired_at: i64,
+    pub trigger_id: i64,
+    pub coin: String,
+    pub currency: String,
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    pub initial_price: f64,
+    pub target_price: f64,
+    pub price: f64,
+}
+
+impl From<&Firing> for HistoryEntry {
+    fn from(firing: &Firing) -> Self {
+        Self {
+            fired_at: firing.fired_at.timestamp(),
+            trigger_id: firing.trigger.rowid,
+            coin: firing.coin.clone(),
+            currency: firing.currency.clone(),
+            group: firing.trigger.meta.group.clone(),
+            note: firing.trigger.meta.note.clone(),
+            labels: firing.trigger.meta.labels.clone(),
+            initial_price: firing.trigger.initial_price,
+            target_price: firing.trigger.target_price,
+            price: firing.price,
+        }
+    }
+}
+
+fn history_file() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let project_dirs = ProjectDirs::from("org", "jna", "jna")
+        .ok_or::<Box<dyn std::error::Error>>(From::from("Failed to get project_dirs!"))?;
+    Ok(project_dirs.data_dir().join("jna_history.jsonl"))
+}
+
+pub async fn append(firings: &[Firing]) -> Result<(), Box<dyn std::error::Error>> {
+    let history_file = history_file()?;
+    if let Some(data_dir) = history_file.parent() {
+        tokio::fs::create_dir_all(data_dir).await?;
+    }
+    let mut lines = String::new();
+    for firing in firings {
+        lines.push_str(&serde_json::to_string(&HistoryEntry::from(firing))?);
+        lines.push('\n');
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(&history_file).await?;
+    file.write_all(lines.as_bytes()).await?;
+    Ok(())
+}
+
+/// Reads every recorded firing, oldest first. Lines that cannot be parsed are skipped.
+pub async fn load() -> Result<Vec<HistoryEntry>, Box<dyn std::error::Error>> {
+    let history_file = history_file()?;
+    if !history_file.exists() {
+        return Ok(Vec::new());
+    }
+    let content = tokio::fs::read_to_string(&history_file).await?;
+    Ok(content.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+}
@@ -0,0 +1,157 @@
+//! Technical indicators, computed from prices ordered by time. Every indicator starts at the first point
+//! with a full window behind it, so the results are shorter than the prices.
+
+use chrono::NaiveDateTime;
+
+/// The Bollinger Bands at one point in time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Band {
+    pub time: NaiveDateTime,
+    pub lower: f64,
+    pub middle: f64,
+    pub upper: f64,
+}
+
+/// The mean of the last `window` prices.
+pub fn sma(points: &[(NaiveDateTime, f64)], window: usize) -> Vec<(NaiveDateTime, f64)> {
+    if window == 0 || points.len() < window {
+        return Vec::new();
+    }
+    let mut sum: f64 = points[..window].iter().map(|(_, price)| price).sum();
+    let mut result = Vec::with_capacity(points.len() - window + 1);
+    result.push((points[window - 1].0, sum / window as f64));
+    for index in window..points.len() {
+        sum += points[index].1 - points[index - window].1;
+        result.push((points[index].0, sum / window as f64));
+    }
+    result
+}
+
+/// The exponential moving average with a smoothing factor of 2 / (window + 1), starting from the mean of the first window.
+pub fn ema(points: &[(NaiveDateTime, f64)], window: usize) -> Vec<(NaiveDateTime, f64)> {
+    if window == 0 || points.len() < window {
+        return Vec::new();
+    }
+    let alpha = 2.0 / (window as f64 + 1.0);
+    let mut value = points[..window].iter().map(|(_, price)| price).sum::<f64>() / window as f64;
+    let mut result = Vec::with_capacity(points.len() - window + 1);
+    result.push((points[window - 1].0, value));
+    for (time, price) in points[window..].iter() {
+        value += alpha * (price - value);
+        result.push((*time, value));
+    }
+    result
+}
+
+/// The mean of the last `window` prices, with bands `deviations` standard deviations above and below it.
+pub fn bollinger(points: &[(NaiveDateTime, f64)], window: usize, deviations: f64) -> Vec<Band> {
+    if window == 0 {
+        return Vec::new();
+    }
+    points.windows(window).map(|window_points| {
+        let count = window_points.len() as f64;
+        let mean = window_points.iter().map(|(_, price)| price).sum::<f64>() / count;
+        let variance = window_points.iter().map(|(_, price)| (price - mean).powi(2)).sum::<f64>() / count;
+        let deviation = variance.sqrt() * deviations;
+        Band {
+            time: window_points[window_points.len() - 1].0,
+            lower: mean - deviation,
+            middle: mean,
+            upper: mean + deviation,
+        }
+    }).collect()
+}
+
+/// Wilder's relative strength index, between 0 and 100. It needs `period` price changes, so it starts at the point after them.
+pub fn rsi(points: &[(NaiveDateTime, f64)], period: usize) -> Vec<(NaiveDateTime, f64)> {
+    if period == 0 || points.len() <= period {
+        return Vec::new();
+    }
+    let changes: Vec<f64> = points.windows(2).map(|pair| pair[1].1 - pair[0].1).collect();
+    let mut gain = changes[..period].iter().map(|change| change.max(0.0)).sum::<f64>() / period as f64;
+    let mut loss = changes[..period].iter().map(|change| (-change).max(0.0)).sum::<f64>() / period as f64;
+    let mut result = Vec::with_capacity(points.len() - period);
+    result.push((points[period].0, rsi_value(gain, loss)));
+    for (index, change) in changes.iter().enumerate().skip(period) {
+        gain = (gain * (period - 1) as f64 + change.max(0.0)) / period as f64;
+        loss = (loss * (period - 1) as f64 + (-change).max(0.0)) / period as f64;
+        result.push((points[index + 1].0, rsi_value(gain, loss)));
+    }
+    result
+}
+
+/// A price that did not move at all is neither overbought nor oversold, so it gets 50.
+fn rsi_value(gain: f64, loss: f64) -> f64 {
+    if loss == 0.0 {
+        if gain == 0.0 { 50.0 } else { 100.0 }
+    } else {
+        100.0 - 100.0 / (1.0 + gain / loss)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(prices: &[f64]) -> Vec<(NaiveDateTime, f64)> {
+        prices.iter().enumerate().map(|(index, price)| (NaiveDateTime::from_timestamp(index as i64 * 60, 0), *price)).collect()
+    }
+
+    fn values(points: &[(NaiveDateTime, f64)]) -> Vec<f64> {
+        points.iter().map(|(_, value)| *value).collect()
+    }
+
+    fn assert_close(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len(), "{:?} != {:?}", actual, expected);
+        for (actual, expected) in actual.iter().zip(expected.iter()) {
+            assert!((actual - expected).abs() < 1e-9, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn sma_averages_each_window() {
+        let prices = points(&[2.0, 4.0, 6.0, 8.0, 20.0]);
+        let result = sma(&prices, 3);
+        assert_close(&values(&result), &[4.0, 6.0, 34.0 / 3.0]);
+        assert_eq!(result[0].0, prices[2].0);
+        assert_eq!(result[2].0, prices[4].0);
+    }
+
+    #[test]
+    fn ema_starts_from_the_first_mean() {
+        // The smoothing factor of a window of 3 is 0.5.
+        let result = ema(&points(&[2.0, 4.0, 6.0, 8.0, 20.0]), 3);
+        assert_close(&values(&result), &[4.0, 6.0, 13.0]);
+    }
+
+    #[test]
+    fn bollinger_bands_are_deviations_from_the_mean() {
+        // The mean is 5 and the population standard deviation is 2.
+        let result = bollinger(&points(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]), 8, 2.0);
+        assert_eq!(result.len(), 1);
+        let band = result[0];
+        assert_close(&[band.lower, band.middle, band.upper], &[1.0, 5.0, 9.0]);
+        assert_close(&[band.upper - band.lower], &[8.0]);
+    }
+
+    #[test]
+    fn rsi_of_one_way_and_flat_prices() {
+        assert_close(&values(&rsi(&points(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]), 3)), &[100.0, 100.0, 100.0]);
+        assert_close(&values(&rsi(&points(&[6.0, 5.0, 4.0, 3.0, 2.0, 1.0]), 3)), &[0.0, 0.0, 0.0]);
+        assert_close(&values(&rsi(&points(&[3.0, 3.0, 3.0, 3.0, 3.0]), 3)), &[50.0, 50.0]);
+    }
+
+    #[test]
+    fn empty_for_a_zero_window_or_too_few_prices() {
+        let prices = points(&[1.0, 2.0, 3.0]);
+        assert!(sma(&prices, 0).is_empty());
+        assert!(sma(&prices, 4).is_empty());
+        assert!(ema(&prices, 0).is_empty());
+        assert!(ema(&prices, 4).is_empty());
+        assert!(bollinger(&prices, 0, 2.0).is_empty());
+        assert!(bollinger(&prices, 4, 2.0).is_empty());
+        assert!(rsi(&prices, 0).is_empty());
+        // Three prices are only two changes.
+        assert!(rsi(&prices, 3).is_empty());
+    }
+}
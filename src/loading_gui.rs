@@ -1,11 +1,8 @@
 use std::{path::PathBuf, rc::Rc, sync::{Arc, RwLock}};
 
 use directories::ProjectDirs;
-use iced::{Application, Clipboard, Column, Command, Text, executor};
-use tokio::fs::OpenOptions;
-
-use notify_rust::{Notification};
-use std::{time, thread};
+use iced::{Application, Clipboard, Column, Command, Subscription, Text, executor};
+use tokio::{fs::OpenOptions, sync::broadcast};
 
 enum State {
     Initilizing,
@@ -19,6 +16,8 @@ pub enum Message {
     SettingsLoaded(crate::settings::Settings),
     CoinsLoaded(Vec<coingecko_requests::data::Coin>),
     CurrenciesLoaded(Vec<coingecko_requests::data::VsCurrency>),
+    TriggerStoreOpened,
+    NotificationsLoaded(crate::notification_center::NotificationCenter),
     CheckTriggersSpawned,
     Error(String),
     GuiMessage(crate::gui::Message)
@@ -28,9 +27,13 @@ pub struct Gui {
     messages: Vec<String>,
     state: State,
     project_dirs: Option<ProjectDirs>,
-    settings: Option<crate::settings::Settings>,
+    settings: Option<Arc<RwLock<crate::settings::Settings>>>,
     coins: Option<Vec<coingecko_requests::data::Coin>>,
     currencies: Option<Vec<coingecko_requests::data::VsCurrency>>,
+    notifications: Arc<RwLock<crate::notification_center::NotificationCenter>>,
+    prices: Arc<RwLock<crate::trigger_engine::PriceSnapshot>>,
+    triggers_file_status: Arc<RwLock<crate::triggers_file::TriggersFileStatus>>,
+    engine_updates: broadcast::Sender<()>,
 }
 
 impl Application for Gui {
@@ -52,6 +55,10 @@ impl Application for Gui {
             settings: None,
             coins: None,
             currencies: None,
+            notifications: Default::default(),
+            prices: Default::default(),
+            triggers_file_status: Default::default(),
+            engine_updates: broadcast::channel(16).0,
         }, Command::perform(load_project_dirs(), unwrap_result))
     }
 
@@ -72,7 +79,7 @@ impl Application for Gui {
                 Command::perform(load_settings(self.project_dirs.clone().unwrap()), unwrap_result)
             }
             Message::SettingsLoaded(settings) => {
-                self.settings = Some(settings);
+                self.settings = Some(Arc::new(RwLock::new(settings)));
                 self.messages.push(format!("Settings have been loaded successfully!"));
                 self.messages.push(format!("Loading coins..."));
                 Command::perform(load_coins(), unwrap_result)
@@ -86,19 +93,34 @@ impl Application for Gui {
             Message::CurrenciesLoaded(vs_currencies) => {
                 self.currencies = Some(vs_currencies);
                 self.messages.push(format!("Currencies have been loaded successfully!"));
+                self.messages.push(format!("Opening the trigger store..."));
+                Command::perform(open_trigger_store(), unwrap_result)
+            }
+            Message::TriggerStoreOpened => {
+                self.messages.push(format!("The trigger store has been opened successfully!"));
+                self.messages.push(format!("Loading notifications..."));
+                Command::perform(load_notifications(), unwrap_result)
+            }
+            Message::NotificationsLoaded(notifications) => {
+                *self.notifications.write().unwrap() = notifications;
+                self.messages.push(format!("Notifications have been loaded successfully!"));
                 self.messages.push(format!("Spawning triggers check..."));
-                Command::perform(spawn_check_triggers(), unwrap_result)
+                Command::perform(spawn_check_triggers(self.settings.clone().unwrap(), self.notifications.clone(), self.prices.clone(), self.triggers_file_status.clone(), self.engine_updates.clone()), unwrap_result)
             }
             Message::CheckTriggersSpawned => {
                 self.messages.push(format!("Triggers have been spawned successfully!"));
                 self.messages.push(format!("Starting the application..."));
-                let settings = Arc::new(RwLock::new(self.settings.take().unwrap()));
+                let settings = self.settings.take().unwrap();
                 let coins = Rc::new(self.coins.take().unwrap());
                 let currencies = Rc::new(self.currencies.take().unwrap());
                 let (gui, gui_message) = crate::gui::Gui::new(crate::gui::Flags {
                     coins,
                     currencies,
                     settings,
+                    notifications: self.notifications.clone(),
+                    prices: self.prices.clone(),
+                    triggers_file_status: self.triggers_file_status.clone(),
+                    engine_updates: self.engine_updates.clone(),
                 });
                 self.state = State::Initialized(gui);
                 gui_message.map(Self::Message::GuiMessage)
@@ -119,6 +141,14 @@ impl Application for Gui {
         }
     }
 
+    fn subscription(&self) -> Subscription<Self::Message> {
+        if let State::Initialized(ref gui) = self.state {
+            gui.subscription().map(Message::GuiMessage)
+        } else {
+            Subscription::none()
+        }
+    }
+
     fn view(&mut self) -> iced::Element<'_, Self::Message> {
         if let State::Initialized(ref mut gui) = self.state {
             gui.view().map(Message::GuiMessage)
@@ -181,53 +211,31 @@ async fn load_vs_currencies() -> Result<Message, Box<dyn std::error::Error>> {
     Ok(Message::CurrenciesLoaded(currencies))
 }
 
-async fn spawn_check_triggers() -> Result<Message, Box<dyn std::error::Error>> {
+/// Migrates the store before anything else uses it, which imports the existing triggers on the first run.
+async fn open_trigger_store() -> Result<Message, Box<dyn std::error::Error>> {
+    crate::trigger_store::prepare().await?;
+    Ok(Message::TriggerStoreOpened)
+}
+
+/// JNA starts with only the error when the notifications cannot be read.
+async fn load_notifications() -> Result<Message, Box<dyn std::error::Error>> {
+    let notifications = crate::notification_center::NotificationCenter::load().await.unwrap_or_else(|err| {
+        let mut notifications = crate::notification_center::NotificationCenter::default();
+        notifications.push(crate::notification_center::NotificationKind::EngineError, format!("Failed to load the notifications"), err.to_string(), None);
+        notifications
+    });
+    Ok(Message::NotificationsLoaded(notifications))
+}
+
+async fn spawn_check_triggers(settings: Arc<RwLock<crate::settings::Settings>>, notifications: Arc<RwLock<crate::notification_center::NotificationCenter>>, prices: Arc<RwLock<crate::trigger_engine::PriceSnapshot>>, triggers_file_status: Arc<RwLock<crate::triggers_file::TriggersFileStatus>>, engine_updates: broadcast::Sender<()>) -> Result<Message, Box<dyn std::error::Error>> {
     let api_client = coingecko_requests::api_client::Client::new();
     let caching_client = coingecko_requests::caching_client::Client::new(api_client).await?;
     let coins = caching_client.coins().await?;
     let currencies = caching_client.vs_currencies().await?;
-    tokio::spawn(async move {
-        loop {
-            println!("Checking triggers");
-            check_triggers(coins.clone(), currencies.clone()).await.unwrap();
-            thread::sleep(time::Duration::from_secs(60));
-        }
-    });
+    crate::notification_center::save_on_change(notifications.clone());
+    let engine = crate::trigger_engine::Engine::new(coins, currencies, settings, notifications.clone(), prices, triggers_file_status, engine_updates)
+        .with_notifier(crate::trigger_engine::DesktopNotifier)
+        .with_notifier(crate::notification_center::InAppNotifier { notifications });
+    tokio::spawn(engine.run());
     Ok(Message::CheckTriggersSpawned)
 }
-
-async fn check_triggers(coins: Vec<coingecko_requests::data::Coin>, currencies: Vec<coingecko_requests::data::VsCurrency>) -> Result<(), Box<dyn std::error::Error>> {
-    let api_client = coingecko_requests::api_client::Client::new();
-    let client = coingecko_requests::caching_client::Client::new(api_client).await?;
-    let triggers = client.get_all_triggers().await?;
-    for trigger in triggers {
-        let mut increase= true;
-        let coin = coins.iter().find(|coin| coin.rowid == trigger.coin_id).cloned().unwrap();
-        let currency = currencies.iter().find(|currency| currency.rowid == trigger.currency_id).cloned().unwrap();
-        
-        if trigger.initial_price > trigger.target_price {
-            increase = false;
-        }
-        let coin_owned = vec![String::from(&coin.raw.id)];
-        let coin_half_owned: Vec<_> = coin_owned.iter().map(String::as_str).collect();
-
-        let currency_owned = vec![String::from(&currency.raw.name)];
-        let currency_half_owned: Vec<_> = currency_owned.iter().map(String::as_str).collect();
-
-        let price = client.price(&coin_half_owned, &currency_half_owned).await?;
-        let price = price[&coin.raw.id][&currency.raw.name];
-        if (increase && price >= trigger.target_price) || (!increase && price <= trigger.target_price){
-            //println!("{} => {}\nOld Price: {}\nNew Price: {}\nChanged: {}", trigger.coin.to_uppercase(), trigger.currency.to_uppercase(), trigger.old_price, price, (price - trigger.old_price as f64).abs());
-    
-            Notification::new()
-                .appname("JNA Monitor")
-                .summary(&format!("{} => {}", coin.raw.id.to_uppercase(), currency.raw.name.to_uppercase()))
-                .body(&format!("Initial Price: {}\nTarget Price: {}\nCurrent Price: {}\nDifference: {}", trigger.initial_price as i64, trigger.target_price as i64, price, (price - trigger.initial_price).abs() as i64))
-                .icon("D:/Projects/Organisation/mywork/JNA_Monitor/icon.png")
-                .show()?;
-            
-            client.delete_trigger(trigger.rowid).await?;
-        } 
-    }
-    Ok(())
-}
@@ -10,9 +10,25 @@ mod loading_gui;
 mod gui;
 mod main_tab_gui;
 mod triggers_tab_gui;
+mod notifications_tab_gui;
 mod settings_tab_gui;
 mod about_tab_gui;
 mod styling;
+mod trigger_engine;
+mod history;
+mod notification_center;
+mod trigger_meta;
+mod trigger_list;
+mod trigger_io;
+mod triggers_file;
+mod trigger_store;
+mod chart_options;
+mod chart;
+mod indicators;
+mod stats;
+mod chart_export;
+mod chart_image;
+mod date_input;
 
 const ICON: &[u8] = include_bytes!("../icon.png");
 
@@ -1,149 +1,148 @@
-use std::{collections::HashMap, fmt::Display, rc::Rc, sync::{Arc, RwLock}};
+use std::{collections::HashMap, path::PathBuf, rc::Rc, sync::{Arc, RwLock}};
 
-use chrono::{Local, NaiveDate, NaiveDateTime};
-use hotplot::chart::line::{self, data::{PlotSettings, PlotThemeSettings, Settings, ThemeSettings}};
-use iced::{Canvas, Clipboard, Column, Command, Container, Element, Length, PickList, Row, Text, pick_list};
-use line::data::DistanceValue;
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, Utc};
+use directories::ProjectDirs;
+use hotplot::chart::line::{self, data::{DistanceValue, PlotSettings, PlotThemeSettings, Settings, ThemeSettings}};
+use iced::{Button, Canvas, Checkbox, Clipboard, Color, Column, Command, Container, Element, Length, PickList, Row, Subscription, Text, TextInput, button, pick_list, text_input};
 
-pub struct Flags {
-    pub coins: Rc<Vec<coingecko_requests::data::Coin>>,
-    pub currencies: Rc<Vec<coingecko_requests::data::VsCurrency>>,
-    pub settings: Arc<RwLock<crate::settings::Settings>>
-}
-
-// #[derive(Debug, Clone, PartialEq, Eq)]
-// pub struct RawCoinWrapper(coingecko_requests::data::RawCoin);
-// #[derive(Debug, Clone, PartialEq, Eq)]
-// pub struct RawVsCurrencyWrapper(coingecko_requests::data::RawVsCurrency);
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum TimePeriod {
-    Daily,
-    Weekly,
-    Monthly,
-    Yearly,
-    All,
-    Custom
-}
-
-impl Default for TimePeriod {
-    fn default() -> Self {
-        Self::Weekly
-    }
-}
-
-impl Display for TimePeriod {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            TimePeriod::Daily => { write!(f, "Daily") }
-            TimePeriod::Weekly => { write!(f, "Weekly") }
-            TimePeriod::Monthly => { write!(f, "Monthly") }
-            TimePeriod::Yearly => { write!(f, "Yearly") }
-            TimePeriod::All => { write!(f, "All") }
-            TimePeriod::Custom => { write!(f, "Custom") }
-        }
-    }
-}
+use crate::{chart::{self, Chart, ChartState, Level, Marker, Series}, chart_export::{self, ExportFormat, ExportSeries, TimestampFormat, Timezone}, chart_image::{self, ImageFormat}, chart_options::{ChartKind, ChartOptions, Indicator, IndicatorKind, SeriesKey, TimePeriod}, date_input, history::HistoryEntry, notification_center::{NotificationKind, NotificationTarget}, stats, trigger_meta::{TriggerMeta, TriggerState}, trigger_store::{self, Trigger}};
 
-#[derive(Debug, Clone, Copy)]
-pub struct DateParts {
-    year: u32,
-    month: u32,
-    day: u32
+/// Everything the market chart endpoint returns for a series, ordered by time.
+#[derive(Debug, Clone, Default)]
+pub struct MarketData {
+    pub prices: Vec<(NaiveDateTime, f64)>,
+    pub volumes: Vec<(NaiveDateTime, f64)>,
+    pub market_caps: Vec<(NaiveDateTime, f64)>,
 }
 
-impl DateParts {
-    fn as_timestamp(&self) -> Option<u64> {
-        NaiveDate::from_ymd_opt(self.year as i32, self.month, self.day)
-            .map(|date| date.and_hms(0, 0, 0)
-            .timestamp() as u64)
-    }
+/// Charts shorter than this cannot be zoomed into any further.
+const MIN_VISIBLE_SECONDS: i64 = 60 * 60;
 
-    fn with_year(&self, year: u32) -> Self {
-        Self {
-            year,
-            ..*self
+impl MarketData {
+    /// Adds the points of `newer` that come at least `spacing` seconds after the last point, so that the
+    /// points stay as far apart as the ones already loaded.
+    fn append(&mut self, newer: MarketData, spacing: i64) {
+        fn append_points(points: &mut Vec<(NaiveDateTime, f64)>, newer: Vec<(NaiveDateTime, f64)>, spacing: i64) {
+            for (time, value) in newer {
+                if points.last().map_or(true, |(last, _)| time.timestamp() - last.timestamp() >= spacing) {
+                    points.push((time, value));
+                }
+            }
         }
+        append_points(&mut self.prices, newer.prices, spacing);
+        append_points(&mut self.volumes, newer.volumes, spacing);
+        append_points(&mut self.market_caps, newer.market_caps, spacing);
     }
 
-    fn with_month(&self, month: u32) -> Self {
-        Self {
-            month,
-            ..*self
+    /// Drops the points before `from`, in unix seconds.
+    fn trim(&mut self, from: i64) {
+        for points in [&mut self.prices, &mut self.volumes, &mut self.market_caps].iter_mut() {
+            points.retain(|(time, _)| time.timestamp() >= from);
         }
     }
+}
 
-    fn with_day(&self, day: u32) -> Self {
-        Self {
-            day,
-            ..*self
-        }
-    }
+pub struct Flags {
+    pub coins: Rc<Vec<coingecko_requests::data::Coin>>,
+    pub currencies: Rc<Vec<coingecko_requests::data::VsCurrency>>,
+    pub settings: Arc<RwLock<crate::settings::Settings>>,
+    pub notifications: Arc<RwLock<crate::notification_center::NotificationCenter>>,
 }
 
-impl TimePeriod {
-    pub fn all() -> Vec<Self> {
-        vec![Self::Daily, Self::Weekly, Self::Monthly, Self::Yearly, Self::All, Self::Custom]
-    }
+// #[derive(Debug, Clone, PartialEq, Eq)]
+// pub struct RawCoinWrapper(coingecko_requests::data::RawCoin);
+// #[derive(Debug, Clone, PartialEq, Eq)]
+// pub struct RawVsCurrencyWrapper(coingecko_requests::data::RawVsCurrency);
 
-    pub fn get_from_to(&self, current: u64, default_from: u64, default_to: u64) -> (u64, u64) {
-        return match self {
-            TimePeriod::Daily => {
-                let to = current - (current % 60);
-                let from = to - 60*60*24;
-                (from, to)
-            }
-            TimePeriod::Weekly => {
-                let to = current - (current % (60*60));
-                let from = to - 60*60*24*7;
-                (from, to)
-            }
-            TimePeriod::Monthly => {
-                let to = current - (current % (60*60*24));
-                let from = to - 60*60*24*30;
-                (from, to)
-            }
-            TimePeriod::Yearly => {
-                let to = current - (current % (60*60*24));
-                let from = to - 60*60*24*365;
-                (from, to)
-            }
-            TimePeriod::All => {
-                let to = current - (current % (60*60*24));
-                let from = 0;
-                (from, to)
-            }
-            TimePeriod::Custom => {
-                (default_from, default_to)
-            }
-        }
-    }
+/// The popover for a trigger asked for on the chart, prefilled with the price under the cursor.
+struct AlertDraft {
+    key: SeriesKey,
+    price_value: String,
+    price_input_state: text_input::State,
+    save_button_state: button::State,
+    cancel_button_state: button::State,
+    error: Option<String>,
 }
 
 pub struct Gui {
     coins: Rc<Vec<coingecko_requests::data::Coin>>,
     currencies: Rc<Vec<coingecko_requests::data::VsCurrency>>,
     settings: Arc<RwLock<crate::settings::Settings>>,
+    notifications: Arc<RwLock<crate::notification_center::NotificationCenter>>,
     time_periods: Vec<TimePeriod>,
     latest_data_request_timestamp: u64,
-    data: Result<Option<Vec<(NaiveDateTime, f64)>>, Box<dyn std::error::Error>>,
-    picked_coin: coingecko_requests::data::Coin,
-    picked_currency: coingecko_requests::data::VsCurrency,
+    /// The range the series were last requested for, so that series added later line up with the others.
+    data_range: (u64, u64),
+    data: HashMap<SeriesKey, Result<MarketData, String>>,
+    data_error: Option<String>,
+    /// Set when the chart options could not be saved. They still apply until the app is closed.
+    settings_error: Option<String>,
+    chart_state: ChartState,
+    /// The current price of every series from CoinGecko's simple-price endpoint, which may be newer than
+    /// the last point on the chart. The loaded ranges can end in the past, so they are not used for it.
+    live_prices: HashMap<SeriesKey, f64>,
+    last_updated: Option<NaiveDateTime>,
+    /// A failed refresh leaves the loaded data alone, so its error is only shown next to the live prices.
+    refresh_error: Option<String>,
+    /// Loaded only while the trigger overlay is shown.
+    triggers: Vec<Trigger>,
+    history: Vec<HistoryEntry>,
+    overlay_error: Option<String>,
+    alert: Option<AlertDraft>,
+    copy_stats_button_state: button::State,
+    export_path: String,
+    export_path_input_state: text_input::State,
+    export_format: ExportFormat,
+    export_format_picklist_state: pick_list::State<ExportFormat>,
+    timestamp_format: TimestampFormat,
+    timestamp_format_picklist_state: pick_list::State<TimestampFormat>,
+    timezone: Timezone,
+    timezone_picklist_state: pick_list::State<Timezone>,
+    export_button_state: button::State,
+    export_status: Option<String>,
+    image_path: String,
+    image_path_input_state: text_input::State,
+    image_format: ImageFormat,
+    image_format_picklist_state: pick_list::State<ImageFormat>,
+    image_width_value: String,
+    image_width_input_state: text_input::State,
+    image_height_value: String,
+    image_height_input_state: text_input::State,
+    export_image_button_state: button::State,
+    /// The series the coin and currency pick lists change.
+    selected_series: usize,
     coin_picklist_state: pick_list::State<coingecko_requests::data::Coin>,
     currency_picklist_state: pick_list::State<coingecko_requests::data::VsCurrency>,
     time_period_packlist_state: pick_list::State<TimePeriod>,
-    date_from_year_picklist_state: pick_list::State<u32>,
-    date_from_month_picklist_state: pick_list::State<u32>,
-    date_from_day_picklist_state: pick_list::State<u32>,
-    date_to_year_picklist_state: pick_list::State<u32>,
-    date_to_month_picklist_state: pick_list::State<u32>,
-    date_to_day_picklist_state: pick_list::State<u32>,
-    years: Vec<u32>,
-    months: Vec<u32>,
-    days: Vec<u32>,
-    time_period: TimePeriod,
-    date_from: DateParts,
-    date_to: DateParts
+    chart_kind_picklist_state: pick_list::State<ChartKind>,
+    date_from_value: String,
+    date_from_input_state: text_input::State,
+    date_to_value: String,
+    date_to_input_state: text_input::State,
+    date_error: Option<String>,
+    apply_dates_button_state: button::State,
+    /// The first day of the month the calendar shows.
+    calendar_month: NaiveDate,
+    /// The start of a range picked on the calendar, until its end is picked too.
+    picked_start: Option<NaiveDateTime>,
+    calendar_previous_button_state: button::State,
+    calendar_next_button_state: button::State,
+    calendar_year_picklist_state: pick_list::State<i32>,
+    calendar_day_button_states: Vec<button::State>,
+    /// The time of the oldest point CoinGecko has for a series. None while it is loading or unknown.
+    /// Failed lookups are removed again, so that the next reload retries them.
+    first_points: HashMap<SeriesKey, Option<NaiveDateTime>>,
+    first_point_error: Option<String>,
+    series_button_states: Vec<(button::State, button::State)>,
+    add_series_button_state: button::State,
+    reset_zoom_button_state: button::State,
+    indicator_kind_picklist_state: pick_list::State<IndicatorKind>,
+    picked_indicator_kind: IndicatorKind,
+    indicator_window_input_state: text_input::State,
+    indicator_window_value: String,
+    indicator_error: Option<String>,
+    add_indicator_button_state: button::State,
+    indicator_button_states: Vec<button::State>,
 }
 
 #[derive(Debug, Clone)]
@@ -151,133 +150,890 @@ pub enum Message {
     CoinPicked(coingecko_requests::data::Coin),
     CurrencyPicked(coingecko_requests::data::VsCurrency),
     TimePeriodPicked(TimePeriod),
-    DataLoaded(Vec<(NaiveDateTime, f64)>, u64),
-    DataLoadFailed(String, u64),
-    ChartMessage(line::data::Message),
-    DateFromYearUpdated(u32),
-    DateFromMonthUpdated(u32),
-    DateFromDayUpdated(u32),
-    DateToYearUpdated(u32),
-    DateToMonthUpdated(u32),
-    DateToDayUpdated(u32),
+    ChartKindPicked(ChartKind),
+    SeriesSelected(usize),
+    SeriesAdded,
+    SeriesRemoved(usize),
+    DataLoaded(SeriesKey, MarketData, u64),
+    DataLoadFailed(SeriesKey, String, u64),
+    ChartMessage(chart::Message),
+    LineChartMessage(line::data::Message),
+    AutoRefreshToggled(bool),
+    TriggersToggled(bool),
+    TriggerOverlayLoaded(Result<(Vec<Trigger>, Vec<HistoryEntry>), String>),
+    AlertPriceChanged(String),
+    AlertSaved,
+    AlertCancelled,
+    AlertCreated(Result<i64, String>),
+    StatsCopied,
+    ExportPathChanged(String),
+    ExportFormatPicked(ExportFormat),
+    TimestampFormatPicked(TimestampFormat),
+    TimezonePicked(Timezone),
+    ExportClicked,
+    Exported(Result<(PathBuf, usize), String>),
+    ImagePathChanged(String),
+    ImageFormatPicked(ImageFormat),
+    ImageWidthChanged(String),
+    ImageHeightChanged(String),
+    ImageExportClicked,
+    ImageExported(Result<PathBuf, String>),
+    RefreshTick,
+    RefreshLoaded(SeriesKey, MarketData, u64),
+    RefreshFailed(SeriesKey, String, u64),
+    LivePriceLoaded(SeriesKey, Result<f64, String>),
+    ZoomReset,
+    NormalizedToggled(bool),
+    LogScaleToggled(bool),
+    VolumeToggled(bool),
+    IndicatorKindPicked(IndicatorKind),
+    IndicatorWindowChanged(String),
+    IndicatorAdded,
+    IndicatorRemoved(usize),
+    MarketCapToggled(bool),
+    DateFromChanged(String),
+    DateToChanged(String),
+    DatesSubmitted,
+    CalendarMonthChanged(NaiveDate),
+    CalendarDayPicked(NaiveDate),
+    FirstPointLoaded(SeriesKey, Result<Option<NaiveDateTime>, String>),
 }
 
 impl Gui {
     pub fn new(flags: Flags) -> (Self, Command<Message>) {
-        let picked_coin = flags.coins.iter().find(|coin| coin.raw.id == "bitcoin").cloned().unwrap();
-        let picked_currency = flags.currencies.iter().find(|currency| currency.raw.name == "usd").cloned().unwrap();
-        let date_from = DateParts {
-            year: 2017,
-            month: 1,
-            day: 1
-        };
-        let date_to = DateParts {
-            year: 2018,
-            month: 1,
-            day: 1
-        };
-        let time_period: TimePeriod = Default::default();
-        let timestamp = Local::now().timestamp() as u64;
-        let (from, to) = time_period.get_from_to(Local::now().timestamp() as u64, date_from.as_timestamp().unwrap(), date_to.as_timestamp().unwrap());
-        println!("From {} to {}", from, to);
-        (Self {
+        let data_dir = ProjectDirs::from("org", "jna", "jna").map(|project_dirs| project_dirs.data_dir().to_path_buf());
+        let export_path = data_dir.as_ref()
+            .map(|data_dir| data_dir.join("jna_chart.csv").to_string_lossy().to_string())
+            .unwrap_or_default();
+        let image_path = data_dir.as_ref()
+            .map(|data_dir| data_dir.join("jna_chart.png").to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut gui = Self {
             coins: flags.coins,
             currencies: flags.currencies,
             settings: flags.settings,
+            notifications: flags.notifications,
             time_periods: TimePeriod::all(),
-            latest_data_request_timestamp: timestamp,
-            data: Ok(None),
-            picked_coin: picked_coin.clone(),
-            picked_currency: picked_currency.clone(),
+            latest_data_request_timestamp: 0,
+            data_range: (0, 0),
+            data: HashMap::new(),
+            data_error: None,
+            settings_error: None,
+            chart_state: Default::default(),
+            live_prices: HashMap::new(),
+            last_updated: None,
+            refresh_error: None,
+            triggers: Vec::new(),
+            history: Vec::new(),
+            overlay_error: None,
+            alert: None,
+            copy_stats_button_state: Default::default(),
+            export_path,
+            export_path_input_state: Default::default(),
+            export_format: ExportFormat::Csv,
+            export_format_picklist_state: Default::default(),
+            timestamp_format: TimestampFormat::UnixSeconds,
+            timestamp_format_picklist_state: Default::default(),
+            timezone: Timezone::Utc,
+            timezone_picklist_state: Default::default(),
+            export_button_state: Default::default(),
+            export_status: None,
+            image_path,
+            image_path_input_state: Default::default(),
+            image_format: ImageFormat::Png,
+            image_format_picklist_state: Default::default(),
+            image_width_value: "1600".to_string(),
+            image_width_input_state: Default::default(),
+            image_height_value: "900".to_string(),
+            image_height_input_state: Default::default(),
+            export_image_button_state: Default::default(),
+            selected_series: 0,
             coin_picklist_state: Default::default(),
             currency_picklist_state: Default::default(),
             time_period_packlist_state: Default::default(),
-            time_period: time_period.clone(),
-            date_from_year_picklist_state: Default::default(),
-            date_from_month_picklist_state: Default::default(),
-            date_from_day_picklist_state: Default::default(),
-            date_to_year_picklist_state: Default::default(),
-            date_to_month_picklist_state: Default::default(),
-            date_to_day_picklist_state: Default::default(),
-            years: (2013..=2021).collect(),
-            months: (1..=12).collect(),
-            days: (1..=31).collect(),
-            date_from,
-            date_to
-        }, Command::perform(load_data(picked_coin.raw.id.clone(), picked_currency.raw.name.clone(), from, to, timestamp), |x| x))
-    }
-
-    pub fn update(&mut self, message: Message, _clipboard: &mut Clipboard) -> Command<Message> {
-        fn update_dates(gui: &mut Gui, new_from_date: DateParts, new_to_date: DateParts) -> Command<Message> {
-            gui.date_from = new_from_date;
-            gui.date_to = new_to_date;
-            if let Some((timestamp_from, timestamp_to)) = new_from_date.as_timestamp().zip(new_to_date.as_timestamp()) {
-                if timestamp_from < timestamp_to {
-                    gui.data = Ok(None);
-                    let timestamp = Local::now().timestamp() as u64;
-                    gui.latest_data_request_timestamp = timestamp;    
-                    return Command::perform(load_data(gui.picked_coin.raw.id.clone(), gui.picked_currency.raw.name.clone(), timestamp_from, timestamp_to, timestamp), |x| x);
-                }
+            chart_kind_picklist_state: Default::default(),
+            date_from_value: String::new(),
+            date_from_input_state: Default::default(),
+            date_to_value: String::new(),
+            date_to_input_state: Default::default(),
+            date_error: None,
+            apply_dates_button_state: Default::default(),
+            calendar_month: NaiveDate::from_ymd(2017, 1, 1),
+            picked_start: None,
+            calendar_previous_button_state: Default::default(),
+            calendar_next_button_state: Default::default(),
+            calendar_year_picklist_state: Default::default(),
+            calendar_day_button_states: Vec::new(),
+            first_points: HashMap::new(),
+            first_point_error: None,
+            series_button_states: Vec::new(),
+            add_series_button_state: Default::default(),
+            reset_zoom_button_state: Default::default(),
+            indicator_kind_picklist_state: Default::default(),
+            picked_indicator_kind: IndicatorKind::Sma,
+            indicator_window_input_state: Default::default(),
+            indicator_window_value: IndicatorKind::Sma.default_window().to_string(),
+            indicator_error: None,
+            add_indicator_button_state: Default::default(),
+            indicator_button_states: Vec::new(),
+        };
+        gui.reset_date_inputs();
+        let command = Command::batch(vec![gui.reload_data(), gui.load_trigger_overlay()]);
+        (gui, command)
+    }
+
+    fn options(&self) -> ChartOptions {
+        self.settings.read().unwrap().chart.clone()
+    }
+
+    fn update_options<F: FnOnce(&mut ChartOptions)>(&mut self, f: F) {
+        f(&mut self.settings.write().unwrap().chart);
+        self.settings_error = self.settings.read().unwrap().save().err().map(|err| format!("The chart options could not be saved: {}", err));
+    }
+
+    /// Requests every series again for the current time period.
+    fn reload_data(&mut self) -> Command<Message> {
+        let options = self.options();
+        let (from, to) = options.time_period.get_from_to(Local::now().timestamp() as u64, options.date_from.timestamp().max(0) as u64, options.date_to.timestamp().max(0) as u64);
+        self.data.clear();
+        if from >= to {
+            self.data_error = Some(format!("Invalid date(s)!"));
+            return Command::none();
+        }
+        println!("From {} to {}", from, to);
+        self.data_error = None;
+        self.data_range = (from, to);
+        self.chart_state.view = None;
+        self.next_request_timestamp();
+        let mut commands: Vec<_> = self.unique_series().into_iter().map(|key| self.load_series(key)).collect();
+        commands.push(self.load_first_points());
+        commands.push(self.load_live_prices());
+        Command::batch(commands)
+    }
+
+    fn load_live_prices(&self) -> Command<Message> {
+        Command::batch(self.unique_series().into_iter().map(|key| Command::perform(load_live_price(key), |x| x)))
+    }
+
+    /// The calendar only goes back to the oldest data of the series on the chart, so that is only needed
+    /// for the Custom period.
+    fn load_first_points(&mut self) -> Command<Message> {
+        if self.options().time_period != TimePeriod::Custom {
+            return Command::none();
+        }
+        let mut commands = Vec::new();
+        for key in self.unique_series() {
+            if !self.first_points.contains_key(&key) {
+                self.first_points.insert(key.clone(), None);
+                commands.push(Command::perform(load_first_point(key), |(key, first_point)| Message::FirstPointLoaded(key, first_point)));
             }
-            gui.data = Err(From::from("Invalid date(s)!"));
+        }
+        Command::batch(commands)
+    }
+
+    /// Shows the dates of the Custom period in the inputs and on the calendar.
+    fn reset_date_inputs(&mut self) {
+        let options = self.options();
+        self.date_from_value = options.date_from.format(date_input::DISPLAY_FORMAT).to_string();
+        self.date_to_value = options.date_to.format(date_input::DISPLAY_FORMAT).to_string();
+        self.date_error = None;
+        self.calendar_month = options.date_from.date().with_day(1).unwrap();
+        self.picked_start = None;
+    }
+
+    fn set_dates(&mut self, from: NaiveDateTime, to: NaiveDateTime) -> Command<Message> {
+        if from >= to {
+            self.date_error = Some(format!("The start has to be before the end!"));
+            return Command::none();
+        }
+        self.update_options(|options| {
+            options.date_from = from;
+            options.date_to = to;
+        });
+        self.date_from_value = from.format(date_input::DISPLAY_FORMAT).to_string();
+        self.date_to_value = to.format(date_input::DISPLAY_FORMAT).to_string();
+        self.date_error = None;
+        self.reload_data()
+    }
+
+    /// From the oldest data of the series on the chart to this year.
+    fn calendar_years(&self) -> Vec<i32> {
+        let first_year = self.unique_series().iter()
+            .filter_map(|key| self.first_points.get(key).cloned().flatten())
+            .map(|first_point| first_point.year())
+            .min()
+            .unwrap_or(FIRST_DATA_YEAR);
+        (first_year..=Utc::now().year()).collect()
+    }
+
+    fn load_trigger_overlay(&self) -> Command<Message> {
+        if self.options().show_triggers {
+            Command::perform(load_trigger_overlay(), Message::TriggerOverlayLoaded)
+        } else {
             Command::none()
         }
+    }
+
+    /// The targets of the triggers for the pair, labelled with their direction.
+    fn levels(&self, key: &SeriesKey) -> Vec<Level> {
+        let coin = self.coins.iter().find(|coin| coin.raw.id == key.coin);
+        let currency = self.currencies.iter().find(|currency| currency.raw.name == key.currency);
+        let (coin, currency) = match (coin, currency) {
+            (Some(coin), Some(currency)) => (coin, currency),
+            _ => return Vec::new(),
+        };
+        let now = Local::now().timestamp();
+        self.triggers.iter()
+            .filter(|trigger| trigger.coin_id == coin.rowid && trigger.currency_id == currency.rowid)
+            .map(|trigger| {
+                let up = trigger.target_price > trigger.initial_price;
+                let mut label = format!("#{} {} to {}", trigger.rowid, if up { "up" } else { "down" }, chart::format_price(trigger.target_price));
+                let state = trigger.meta.state(now);
+                if state != TriggerState::Active {
+                    label = format!("{} ({})", label, state);
+                }
+                Level {
+                    price: trigger.target_price,
+                    label,
+                    up,
+                }
+            })
+            .collect()
+    }
+
+    fn markers(&self, key: &SeriesKey) -> Vec<Marker> {
+        self.history.iter()
+            .filter(|entry| entry.coin == key.coin && entry.currency == key.currency)
+            .filter_map(|entry| Some(Marker {
+                time: NaiveDateTime::from_timestamp_opt(entry.fired_at, 0)?,
+                price: entry.price,
+                label: format!("#{}", entry.trigger_id),
+                up: entry.target_price > entry.initial_price,
+            }))
+            .collect()
+    }
+
+    /// The chart of every loaded series, or None while there is nothing to draw.
+    fn chart(&self) -> Option<Chart> {
+        let lock = self.settings.read().unwrap();
+        let (theme, graph_color) = (lock.theme, lock.graph_color);
+        drop(lock);
+        let options = self.options();
+        let series: Vec<Series> = options.series.iter().enumerate().filter_map(|(index, key)| match self.data.get(key) {
+            Some(Ok(data)) if !data.prices.is_empty() => Some(Series {
+                label: key.to_string(),
+                color: crate::styling::series_color(index, graph_color),
+                points: data.prices.clone(),
+                volumes: data.volumes.clone(),
+                market_caps: data.market_caps.clone(),
+                levels: if options.show_triggers { self.levels(key) } else { Vec::new() },
+                markers: if options.show_triggers { self.markers(key) } else { Vec::new() },
+            }),
+            _ => None,
+        }).collect();
+        if series.is_empty() {
+            return None;
+        }
+        Some(Chart::new(chart_title(&options), theme, series, &options, self.chart_state))
+    }
+
+    /// The plain line view is drawn by hotplot's line chart. Percentages, the log axis, candles, panes,
+    /// indicators, trigger lines and zooming are beyond it, so any of them switches to `chart()` instead.
+    fn line_chart(&self) -> Option<line::Chart> {
+        let options = self.options();
+        if options.kind != ChartKind::Line || options.normalized || options.log_scale || options.show_triggers
+            || !options.indicators.is_empty() || !options.panes().is_empty() || self.chart_state.view.is_some() {
+            return None;
+        }
+        let lock = self.settings.read().unwrap();
+        let (theme, graph_color) = (lock.theme, lock.graph_color);
+        drop(lock);
+        let loaded: Vec<(usize, &Vec<(NaiveDateTime, f64)>)> = options.series.iter().enumerate().filter_map(|(index, key)| match self.data.get(key) {
+            Some(Ok(data)) if !data.prices.is_empty() => Some((index, &data.prices)),
+            _ => None,
+        }).collect();
+        // Every series shares the x-range and y-range, which span all of them.
+        let points = || loaded.iter().flat_map(|(_, prices)| prices.iter());
+        let min_x_value = points().map(|(time, _)| *time).min()?;
+        let max_x_value = points().map(|(time, _)| *time).max()?;
+        let min_y_value = points().map(|(_, price)| *price).min_by(|f1, f2| f1.total_cmp(f2))?;
+        let max_y_value = points().map(|(_, price)| *price).max_by(|f1, f2| f1.total_cmp(f2))?;
+        let plot_data = loaded.iter().map(|(index, prices)| {
+            let color = crate::styling::series_color(*index, graph_color);
+            let plot_settings = PlotSettings {
+                theme: PlotThemeSettings {
+                    line_color: color,
+                    point_color: color,
+                },
+                point_size1: 4.0,
+                point_size2: 5.5,
+                point_size3: 7.0,
+                ..Default::default()
+            };
+            (plot_settings, (*prices).clone())
+        }).collect();
+        let settings = Settings {
+            theme: ThemeSettings::from(theme),
+            title: Some(chart_title(&options)),
+            min_x_label_distance: DistanceValue::Fixed(MIN_X_LABEL_DISTANCE),
+            ..Default::default()
+        };
+        Some(line::Chart::new(settings, min_x_value, max_x_value, min_y_value, max_y_value, plot_data))
+    }
+
+    /// The stats panel for the selected series, over the visible range so that it follows the zoom.
+    fn stats_lines(&self) -> Option<Vec<String>> {
+        let options = self.options();
+        let key = &options.series[self.selected_index(&options)];
+        let data = match self.data.get(key) {
+            Some(Ok(data)) => data,
+            _ => return None,
+        };
+        let (from, to) = self.visible_range();
+        let points: Vec<_> = data.prices.iter()
+            .filter(|(time, _)| time.timestamp() >= from && time.timestamp() <= to)
+            .cloned()
+            .collect();
+        let summary = stats::summarize(&points)?;
+        let describe = |(time, price): (NaiveDateTime, f64)| format!("{} at {}", chart::format_price(price), time.format("%Y-%m-%d %H:%M"));
+        let percent = |value: Option<f64>| value.map_or(format!("-"), |value| format!("{:.2}%", value));
+        Some(vec![
+            format!("{}", key),
+            format!("Open: {}", describe(summary.open)),
+            format!("Close: {}", describe(summary.close)),
+            format!("High: {}", describe(summary.high)),
+            format!("Low: {}", describe(summary.low)),
+            format!("Change: {} ({})", chart::format_price(summary.change), summary.change_percent.map_or(format!("-"), |value| format!("{:+.2}%", value))),
+            format!("Mean: {}", chart::format_price(summary.mean)),
+            format!("Std. dev. of returns: {}", percent(summary.return_stddev)),
+            format!("Annualized volatility: {}", percent(summary.annualized_volatility)),
+            format!("Max drawdown: {:.2}%", summary.max_drawdown),
+        ])
+    }
+
+    fn unique_series(&self) -> Vec<SeriesKey> {
+        let mut series = Vec::new();
+        for key in self.options().series {
+            if !series.contains(&key) {
+                series.push(key);
+            }
+        }
+        series
+    }
+
+    /// Every request gets a newer timestamp than the one before, even within the same second, so that
+    /// the responses to quick zooming can still be told apart.
+    fn next_request_timestamp(&mut self) -> u64 {
+        self.latest_data_request_timestamp = (Local::now().timestamp() as u64).max(self.latest_data_request_timestamp + 1);
+        self.latest_data_request_timestamp
+    }
+
+    fn visible_range(&self) -> (i64, i64) {
+        self.chart_state.view.unwrap_or((self.data_range.0 as i64, self.data_range.1 as i64))
+    }
+
+    /// Shows the given range on the chart. Data for it is requested when the loaded data does not cover
+    /// it, or is coarser than what CoinGecko has for a range that short. The loaded data stays on the chart
+    /// until the new data arrives.
+    fn set_view(&mut self, from: i64, to: i64) -> Command<Message> {
+        let now = Local::now().timestamp();
+        let (from, to) = if to > now { (from - (to - now), now) } else { (from, to) };
+        self.chart_state.view = Some((from, to));
+
+        let (loaded_from, loaded_to) = (self.data_range.0 as i64, self.data_range.1 as i64);
+        let (spacing, longest) = granularity(to - from);
+        if from >= loaded_from && to <= loaded_to && granularity(loaded_to - loaded_from).0 <= spacing {
+            return Command::none();
+        }
+        // Some extra on both sides, so that panning a little does not need another request right away.
+        let margin = ((to - from) * 2).min(longest).saturating_sub(to - from) / 2;
+        let from = (from - margin).max(0);
+        let to = (to + margin).min(now);
+        self.data_range = (from as u64, to as u64);
+        let timestamp = self.next_request_timestamp();
+        Command::batch(self.unique_series().into_iter().map(|key| Command::perform(load_data(key, from as u64, to as u64, timestamp), |x| x)))
+    }
+
+    /// Requests a single series for the range the other series were loaded for.
+    fn load_series(&mut self, key: SeriesKey) -> Command<Message> {
+        self.data.remove(&key);
+        let (from, to) = self.data_range;
+        Command::perform(load_data(key, from, to, self.latest_data_request_timestamp), |x| x)
+    }
+
+    /// The index of the selected series, which may have been removed since it was selected.
+    fn selected_index(&self, options: &ChartOptions) -> usize {
+        self.selected_series.min(options.series.len().saturating_sub(1))
+    }
+
+    /// Replaces the selected series, or selects the given pair if it is already on the chart.
+    fn set_selected_series(&mut self, key: SeriesKey) -> Command<Message> {
+        let options = self.options();
+        if let Some(index) = options.series.iter().position(|series| *series == key) {
+            self.selected_series = index;
+            return Command::none();
+        }
+        let selected = self.selected_index(&options);
+        self.selected_series = selected;
+        self.update_options(|options| options.series[selected] = key.clone());
+        Command::batch(vec![self.load_series(key.clone()), self.load_first_points(), Command::perform(load_live_price(key), |x| x)])
+    }
+
+    /// Shows the given coin/currency pair on the chart, e.g. when a notification is opened.
+    pub fn show_pair(&mut self, coin: &str, currency: &str) -> Command<Message> {
+        self.set_selected_series(SeriesKey {
+            coin: coin.to_string(),
+            currency: currency.to_string(),
+        })
+    }
+
+    /// Refreshes the chart periodically when auto-refresh is on.
+    pub fn subscription(&self) -> Subscription<Message> {
+        let options = self.options();
+        match options.time_period.refresh_interval() {
+            Some(interval) if options.auto_refresh => iced::time::every(interval).map(|_| Message::RefreshTick),
+            _ => Subscription::none(),
+        }
+    }
+
+    pub fn update(&mut self, message: Message, clipboard: &mut Clipboard) -> Command<Message> {
+        let options = self.options();
         match message {
             Message::CoinPicked(picked) => {
-                let timestamp = Local::now().timestamp() as u64;
-                self.latest_data_request_timestamp = timestamp;
-                self.picked_coin = picked;
-                self.data = Ok(None);
-                let (from, to) = self.time_period.get_from_to(Local::now().timestamp() as u64, self.date_from.as_timestamp().unwrap(), self.date_to.as_timestamp().unwrap());
-                Command::perform(load_data(self.picked_coin.raw.id.clone(), self.picked_currency.raw.name.clone(), from, to, timestamp), |x| x)
+                let currency = options.series[self.selected_index(&options)].currency.clone();
+                self.set_selected_series(SeriesKey {
+                    coin: picked.raw.id,
+                    currency,
+                })
             }
             Message::CurrencyPicked(picked) => {
-                let timestamp = Local::now().timestamp() as u64;
-                self.latest_data_request_timestamp = timestamp;
-                self.picked_currency = picked;
-                self.data = Ok(None);
-                let (from, to) = self.time_period.get_from_to(Local::now().timestamp() as u64, self.date_from.as_timestamp().unwrap(), self.date_to.as_timestamp().unwrap());
-                Command::perform(load_data(self.picked_coin.raw.id.clone(), self.picked_currency.raw.name.clone(), from, to, timestamp), |x| x)
+                let coin = options.series[self.selected_index(&options)].coin.clone();
+                self.set_selected_series(SeriesKey {
+                    coin,
+                    currency: picked.raw.name,
+                })
             }
             Message::TimePeriodPicked(picked) => {
-                let timestamp = Local::now().timestamp() as u64;
-                self.latest_data_request_timestamp = timestamp;
-                self.time_period = picked;
-                self.data = Ok(None);
-                let (from, to) = self.time_period.get_from_to(Local::now().timestamp() as u64, self.date_from.as_timestamp().unwrap(), self.date_to.as_timestamp().unwrap());
-                Command::perform(load_data(self.picked_coin.raw.id.clone(), self.picked_currency.raw.name.clone(), from, to, timestamp), |x| x)
-            }
-            Message::DataLoaded(data, timestamp) => {
+                self.update_options(|options| options.time_period = picked);
+                self.reset_date_inputs();
+                self.reload_data()
+            }
+            Message::ChartKindPicked(picked) => {
+                self.update_options(|options| options.kind = picked);
+                Command::none()
+            }
+            Message::SeriesSelected(index) => {
+                self.selected_series = index.min(options.series.len() - 1);
+                Command::none()
+            }
+            Message::SeriesAdded => {
+                // The new series starts out as the selected coin in another currency, or another coin in the
+                // selected currency, and is then changed with the pick lists. Every pair is only on the chart once.
+                let selected = &options.series[self.selected_index(&options)];
+                let in_other_currencies = self.currencies.iter().map(|currency| SeriesKey {
+                    coin: selected.coin.clone(),
+                    currency: currency.raw.name.clone(),
+                });
+                let in_other_coins = self.coins.iter().map(|coin| SeriesKey {
+                    coin: coin.raw.id.clone(),
+                    currency: selected.currency.clone(),
+                });
+                let key = match in_other_currencies.chain(in_other_coins).find(|key| !options.series.contains(key)) {
+                    Some(key) => key,
+                    None => return Command::none(),
+                };
+                self.update_options(|options| options.series.push(key.clone()));
+                self.selected_series = options.series.len();
+                Command::batch(vec![self.load_series(key.clone()), self.load_first_points(), Command::perform(load_live_price(key), |x| x)])
+            }
+            Message::SeriesRemoved(index) => {
+                if options.series.len() > 1 && index < options.series.len() {
+                    self.update_options(|options| { options.series.remove(index); });
+                    if self.selected_series >= index && self.selected_series > 0 {
+                        self.selected_series -= 1;
+                    }
+                }
+                Command::none()
+            }
+            Message::DataLoaded(key, data, timestamp) => {
                 if self.latest_data_request_timestamp == timestamp {
-                    self.data = Ok(Some(data));
+                    self.data.insert(key, Ok(data));
+                }
+                Command::none()
+            }
+            Message::AutoRefreshToggled(auto_refresh) => {
+                self.update_options(|options| options.auto_refresh = auto_refresh);
+                Command::none()
+            }
+            Message::TriggersToggled(show_triggers) => {
+                self.update_options(|options| options.show_triggers = show_triggers);
+                self.load_trigger_overlay()
+            }
+            Message::TriggerOverlayLoaded(result) => {
+                match result {
+                    Ok((triggers, history)) => {
+                        self.triggers = triggers;
+                        self.history = history;
+                        self.overlay_error = None;
+                    }
+                    Err(err) => {
+                        self.overlay_error = Some(format!("Failed to load the triggers: {}", err));
+                    }
+                }
+                Command::none()
+            }
+            Message::RefreshTick => {
+                // Only the time since the last loaded point is requested. The request timestamp is not
+                // renewed, so a refresh never makes a reload that is going on look stale.
+                let now = Local::now().timestamp() as u64;
+                let timestamp = self.latest_data_request_timestamp;
+                let mut commands = vec![self.load_trigger_overlay(), self.load_live_prices()];
+                // A zoomed or panned view does not end now, so new points would only land outside of it.
+                if self.chart_state.view.is_some() {
+                    return Command::batch(commands);
+                }
+                for key in self.unique_series() {
+                    if let Some(Ok(data)) = self.data.get(&key) {
+                        let from = data.prices.last().map_or(self.data_range.1, |(time, _)| time.timestamp() as u64);
+                        commands.push(Command::perform(load_data(key, from, now, timestamp), |message| match message {
+                            Message::DataLoaded(key, data, timestamp) => Message::RefreshLoaded(key, data, timestamp),
+                            Message::DataLoadFailed(key, err, timestamp) => Message::RefreshFailed(key, err, timestamp),
+                            message => message,
+                        }));
+                    }
+                }
+                Command::batch(commands)
+            }
+            Message::RefreshLoaded(key, data, timestamp) => {
+                if self.latest_data_request_timestamp == timestamp && self.chart_state.view.is_none() {
+                    // The range slides along instead of growing, so that the points keep the spacing of the period.
+                    let (from, to) = self.data_range;
+                    let (spacing, _) = granularity((to - from) as i64);
+                    let now = (Local::now().timestamp() as u64).max(to);
+                    let from = from + (now - to);
+                    if let Some(Ok(existing)) = self.data.get_mut(&key) {
+                        existing.append(data, spacing);
+                        existing.trim(from as i64);
+                    }
+                    self.data_range = (from, now);
+                    self.refresh_error = None;
+                }
+                Command::none()
+            }
+            Message::LivePriceLoaded(key, result) => {
+                match result {
+                    Ok(price) => {
+                        self.live_prices.insert(key, price);
+                        self.last_updated = Some(Local::now().naive_local());
+                    }
+                    Err(err) => {
+                        self.refresh_error = Some(format!("Failed to get the price of {}: {}", key, err));
+                    }
                 }
                 Command::none()
             }
-            Message::DataLoadFailed(err, timestamp) => {
+            Message::RefreshFailed(key, err, timestamp) => {
                 if self.latest_data_request_timestamp == timestamp {
-                    self.data = Err(err.into());
+                    self.refresh_error = Some(format!("Failed to refresh {}: {}", key, err));
                 }
                 Command::none()
             }
-            Message::DateFromYearUpdated(new_year) => {
-                update_dates(self, self.date_from.with_year(new_year), self.date_to)
+            Message::DataLoadFailed(key, err, timestamp) => {
+                if self.latest_data_request_timestamp == timestamp {
+                    self.notifications.write().unwrap().push(
+                        NotificationKind::DataLoadFailed,
+                        format!("Failed to load {} to {} data", key.coin, key.currency),
+                        err.clone(),
+                        Some(NotificationTarget::Chart { coin: key.coin.clone(), currency: key.currency.clone() }));
+                    self.data.insert(key, Err(err));
+                }
+                Command::none()
             }
-            Message::DateFromMonthUpdated(new_month) => {
-                update_dates(self, self.date_from.with_month(new_month), self.date_to)
+            Message::DateFromChanged(value) => {
+                self.date_from_value = value;
+                self.date_error = None;
+                Command::none()
             }
-            Message::DateFromDayUpdated(new_day) => {
-                update_dates(self, self.date_from.with_day(new_day), self.date_to)
+            Message::DateToChanged(value) => {
+                self.date_to_value = value;
+                self.date_error = None;
+                Command::none()
             }
-            Message::DateToYearUpdated(new_year) => {
-                update_dates(self, self.date_from, self.date_to.with_year(new_year))
+            Message::DatesSubmitted => {
+                let now = Utc::now().naive_utc();
+                match (date_input::parse(&self.date_from_value, now), date_input::parse_end(&self.date_to_value, now)) {
+                    (Ok(from), Ok(to)) => {
+                        self.calendar_month = from.date().with_day(1).unwrap();
+                        self.picked_start = None;
+                        self.set_dates(from, to)
+                    }
+                    (Err(err), _) | (_, Err(err)) => {
+                        self.date_error = Some(err);
+                        Command::none()
+                    }
+                }
             }
-            Message::DateToMonthUpdated(new_month) => {
-                update_dates(self, self.date_from, self.date_to.with_month(new_month))
+            Message::CalendarMonthChanged(month) => {
+                self.calendar_month = month;
+                Command::none()
             }
-            Message::DateToDayUpdated(new_day) => {
-                update_dates(self, self.date_from, self.date_to.with_day(new_day))
+            Message::CalendarDayPicked(day) => {
+                // The first pick starts the range and the second one ends it, including the picked day.
+                let start = day.and_hms(0, 0, 0);
+                match self.picked_start.take() {
+                    Some(from) if start >= from => {
+                        self.set_dates(from, start + Duration::days(1))
+                    }
+                    _ => {
+                        self.picked_start = Some(start);
+                        self.date_from_value = start.format(date_input::DISPLAY_FORMAT).to_string();
+                        self.date_error = None;
+                        Command::none()
+                    }
+                }
+            }
+            Message::FirstPointLoaded(key, Ok(first_point)) => {
+                self.first_points.insert(key, first_point);
+                self.first_point_error = None;
+                Command::none()
+            }
+            Message::FirstPointLoaded(key, Err(err)) => {
+                self.first_point_error = Some(format!("Failed to find the oldest data of {}, the calendar may start too early: {}", key, err));
+                self.first_points.remove(&key);
+                Command::none()
+            }
+            Message::ChartMessage(chart::Message::Zoomed { anchor, factor }) => {
+                let (from, to) = self.visible_range();
+                let from = anchor - ((anchor - from) as f64 * factor as f64) as i64;
+                let to = anchor + ((to - anchor) as f64 * factor as f64) as i64;
+                if to - from < MIN_VISIBLE_SECONDS {
+                    return Command::none();
+                }
+                self.set_view(from, to)
+            }
+            Message::ChartMessage(chart::Message::DragStarted(position)) => {
+                self.chart_state.drag = Some(position);
+                Command::none()
+            }
+            Message::ChartMessage(chart::Message::Panned { by, position }) => {
+                self.chart_state.drag = Some(position);
+                let (from, to) = self.visible_range();
+                self.set_view(from + by, to + by)
+            }
+            Message::ChartMessage(chart::Message::ModifiersChanged(modifiers)) => {
+                self.chart_state.modifiers = modifiers;
+                Command::none()
+            }
+            Message::ChartMessage(chart::Message::AlertRequested { series, price }) => {
+                if let Some(key) = options.series.iter().find(|key| key.to_string() == series) {
+                    self.alert = Some(AlertDraft {
+                        key: key.clone(),
+                        price_value: chart::format_price(price),
+                        price_input_state: text_input::State::focused(),
+                        save_button_state: Default::default(),
+                        cancel_button_state: Default::default(),
+                        error: None,
+                    });
+                    // The target is checked against a fresh price, which also shows up in the live prices.
+                    return Command::perform(load_live_price(key.clone()), |x| x);
+                }
+                Command::none()
+            }
+            Message::AlertPriceChanged(value) => {
+                if let Some(alert) = self.alert.as_mut() {
+                    alert.price_value = value;
+                    alert.error = None;
+                }
+                Command::none()
+            }
+            Message::AlertSaved => {
+                let alert = match self.alert.as_mut() {
+                    Some(alert) => alert,
+                    None => return Command::none(),
+                };
+                let coin = self.coins.iter().find(|coin| coin.raw.id == alert.key.coin).cloned();
+                let currency = self.currencies.iter().find(|currency| currency.raw.name == alert.key.currency).cloned();
+                // The warning of the target is shown while it is typed.
+                match (coin, currency, crate::triggers_tab_gui::check_target(&alert.price_value, None)) {
+                    (Some(coin), Some(currency), Ok((target, _))) => {
+                        // Without an initial price the current one is fetched, which decides whether the trigger waits for a rise or a fall.
+                        Command::perform(trigger_store::add(coin, currency, None, target, TriggerMeta::default()), |result| Message::AlertCreated(result.map_err(|err| err.to_string())))
+                    }
+                    (_, _, Err(err)) => {
+                        alert.error = Some(err);
+                        Command::none()
+                    }
+                    _ => {
+                        alert.error = Some(format!("Unknown coin or currency!"));
+                        Command::none()
+                    }
+                }
+            }
+            Message::StatsCopied => {
+                if let Some(lines) = self.stats_lines() {
+                    clipboard.write(lines.join("\n"));
+                }
+                Command::none()
+            }
+            Message::ExportPathChanged(value) => {
+                self.export_path = value;
+                Command::none()
+            }
+            Message::ExportFormatPicked(picked) => {
+                self.export_format = picked;
+                self.export_path = PathBuf::from(&self.export_path).with_extension(picked.extension()).to_string_lossy().to_string();
+                Command::none()
+            }
+            Message::TimestampFormatPicked(picked) => {
+                self.timestamp_format = picked;
+                Command::none()
+            }
+            Message::TimezonePicked(picked) => {
+                self.timezone = picked;
+                Command::none()
+            }
+            Message::ExportClicked => {
+                // Exactly what the chart shows: every series, within the visible range.
+                let (from, to) = self.visible_range();
+                let visible = |points: &Vec<(NaiveDateTime, f64)>| points.iter()
+                    .filter(|(time, _)| time.timestamp() >= from && time.timestamp() <= to)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                let series = self.unique_series().into_iter().filter_map(|key| match self.data.get(&key) {
+                    Some(Ok(data)) => Some(ExportSeries {
+                        prices: visible(&data.prices),
+                        volumes: visible(&data.volumes),
+                        market_caps: visible(&data.market_caps),
+                        coin: key.coin,
+                        currency: key.currency,
+                    }),
+                    _ => None,
+                }).collect();
+                let path = PathBuf::from(&self.export_path);
+                Command::perform(chart_export::export(path, series, self.export_format, self.timestamp_format, self.timezone), |result| Message::Exported(result.map_err(|err| err.to_string())))
+            }
+            Message::Exported(Ok((path, count))) => {
+                self.export_path = path.to_string_lossy().to_string();
+                self.export_status = Some(format!("Exported {} point(s) to {}", count, self.export_path));
+                Command::none()
+            }
+            Message::Exported(Err(err)) => {
+                self.export_status = Some(format!("Export failed: {}", err));
+                Command::none()
             }
-            Message::ChartMessage(_) => {
+            Message::ImagePathChanged(value) => {
+                self.image_path = value;
+                Command::none()
+            }
+            Message::ImageFormatPicked(picked) => {
+                self.image_format = picked;
+                self.image_path = PathBuf::from(&self.image_path).with_extension(picked.extension()).to_string_lossy().to_string();
+                Command::none()
+            }
+            Message::ImageWidthChanged(value) => {
+                self.image_width_value = value;
+                Command::none()
+            }
+            Message::ImageHeightChanged(value) => {
+                self.image_height_value = value;
+                Command::none()
+            }
+            Message::ImageExportClicked => {
+                let size = (self.image_width_value.trim().parse::<u32>(), self.image_height_value.trim().parse::<u32>());
+                match (self.chart(), size) {
+                    (Some(chart), (Ok(width), Ok(height))) if width >= 100 && height >= 100 => {
+                        let path = PathBuf::from(&self.image_path);
+                        Command::perform(chart_image::export(path, chart.with_legend(), self.image_format, width, height), |result| Message::ImageExported(result.map_err(|err| err.to_string())))
+                    }
+                    (None, _) => {
+                        self.export_status = Some(format!("There is no chart to export!"));
+                        Command::none()
+                    }
+                    _ => {
+                        self.export_status = Some(format!("The width and the height have to be whole numbers of at least 100 pixels!"));
+                        Command::none()
+                    }
+                }
+            }
+            Message::ImageExported(Ok(path)) => {
+                self.image_path = path.to_string_lossy().to_string();
+                self.export_status = Some(format!("Exported the chart to {}", self.image_path));
+                Command::none()
+            }
+            Message::ImageExported(Err(err)) => {
+                self.export_status = Some(format!("Export failed: {}", err));
+                Command::none()
+            }
+            Message::AlertCancelled => {
+                self.alert = None;
+                Command::none()
+            }
+            Message::AlertCreated(Ok(_)) => {
+                self.alert = None;
+                self.load_trigger_overlay()
+            }
+            Message::AlertCreated(Err(err)) => {
+                if let Some(alert) = self.alert.as_mut() {
+                    alert.error = Some(format!("The trigger could not be saved: {}", err));
+                }
+                Command::none()
+            }
+            Message::ChartMessage(chart::Message::DragEnded) => {
+                self.chart_state.drag = None;
+                Command::none()
+            }
+            Message::LineChartMessage(_) => {
+                Command::none()
+            }
+            Message::ZoomReset => {
+                self.reload_data()
+            }
+            Message::NormalizedToggled(normalized) => {
+                self.update_options(|options| options.normalized = normalized);
+                Command::none()
+            }
+            Message::LogScaleToggled(log_scale) => {
+                self.update_options(|options| options.log_scale = log_scale);
+                Command::none()
+            }
+            Message::VolumeToggled(show_volume) => {
+                self.update_options(|options| options.show_volume = show_volume);
+                Command::none()
+            }
+            Message::IndicatorKindPicked(picked) => {
+                self.picked_indicator_kind = picked;
+                self.indicator_window_value = picked.default_window().to_string();
+                self.indicator_error = None;
+                Command::none()
+            }
+            Message::IndicatorWindowChanged(value) => {
+                self.indicator_window_value = value;
+                self.indicator_error = None;
+                Command::none()
+            }
+            Message::IndicatorAdded => {
+                match self.indicator_window_value.trim().parse::<u32>() {
+                    Ok(window) if window >= 2 => {
+                        let indicator = Indicator {
+                            kind: self.picked_indicator_kind,
+                            window,
+                        };
+                        if !options.indicators.contains(&indicator) {
+                            self.update_options(|options| options.indicators.push(indicator));
+                        }
+                    }
+                    _ => {
+                        self.indicator_error = Some(format!("The window has to be a whole number of at least 2 points!"));
+                    }
+                }
+                Command::none()
+            }
+            Message::IndicatorRemoved(index) => {
+                self.update_options(|options| { options.indicators.remove(index); });
+                Command::none()
+            }
+            Message::MarketCapToggled(show_market_cap) => {
+                self.update_options(|options| options.show_market_cap = show_market_cap);
                 Command::none()
             }
         }
@@ -286,10 +1042,23 @@ impl Gui {
     pub fn view(&mut self) -> iced::Element<'_, Message> {
         let lock = self.settings.read().unwrap();
         let theme = lock.theme;
+        let graph_color = lock.graph_color;
+        let options = lock.chart.clone();
         let show_all_coins = lock.show_all_coins;
         let show_all_currencies = lock.show_all_currencies;
         let coins = if show_all_coins { self.coins.as_ref().clone() } else { self.coins.iter().filter(|coin| coin.favourite).cloned().collect() };
         let currencies = if show_all_currencies { self.currencies.as_ref().clone() } else { self.currencies.iter().filter(|coin| coin.favourite).cloned().collect() };
+        drop(lock);
+
+        let selected = &options.series[self.selected_index(&options)];
+        let picked_coin = self.coins.iter().find(|coin| coin.raw.id == selected.coin).cloned();
+        let picked_currency = self.currencies.iter().find(|currency| currency.raw.name == selected.currency).cloned();
+        let canvas: Option<Element<_>> = match self.line_chart() {
+            Some(line_chart) => Some(Element::from(Canvas::new(line_chart).width(Length::Fill).height(Length::Fill)).map(Message::LineChartMessage)),
+            None => self.chart().map(|chart| Element::from(Canvas::new(chart).width(Length::Fill).height(Length::Fill)).map(Message::ChartMessage)),
+        };
+        let stats_lines = self.stats_lines();
+        let calendar_years = self.calendar_years();
 
         let mut main_column = Column::new().spacing(5);
 
@@ -297,144 +1066,322 @@ impl Gui {
 
         let mut coin_column = Column::new().spacing(5).width(Length::FillPortion(1));
         coin_column = coin_column.push(Text::new("Coin"));
-        let coin_picklist = PickList::new(&mut self.coin_picklist_state, coins, Some(self.picked_coin.clone()), Message::CoinPicked).width(Length::Fill).style(theme);
+        let coin_picklist = PickList::new(&mut self.coin_picklist_state, coins, picked_coin, Message::CoinPicked).width(Length::Fill).style(theme);
         coin_column = coin_column.push(coin_picklist);
 
         let mut vs_currency_column = Column::new().spacing(5).width(Length::FillPortion(1));
         vs_currency_column = vs_currency_column.push(Text::new("Currency"));
-        let vs_currency_picklist = PickList::new(&mut self.currency_picklist_state, currencies, Some(self.picked_currency.clone()), Message::CurrencyPicked).width(Length::Fill).style(theme);
+        let vs_currency_picklist = PickList::new(&mut self.currency_picklist_state, currencies, picked_currency, Message::CurrencyPicked).width(Length::Fill).style(theme);
         vs_currency_column = vs_currency_column.push(vs_currency_picklist);
 
         let mut time_period_column = Column::new().spacing(5).width(Length::FillPortion(1));
         time_period_column = time_period_column.push(Text::new("Time period"));
-        let time_period_picklist = PickList::new(&mut self.time_period_packlist_state, &self.time_periods, Some(self.time_period.clone()), Message::TimePeriodPicked).width(Length::Fill).style(theme);
+        let time_period_picklist = PickList::new(&mut self.time_period_packlist_state, &self.time_periods, Some(options.time_period), Message::TimePeriodPicked).width(Length::Fill).style(theme);
         time_period_column = time_period_column.push(time_period_picklist);
-        
+
+        let mut chart_kind_column = Column::new().spacing(5).width(Length::FillPortion(1));
+        chart_kind_column = chart_kind_column.push(Text::new("Chart"));
+        let chart_kind_picklist = PickList::new(&mut self.chart_kind_picklist_state, &ChartKind::ALL[..], Some(options.kind), Message::ChartKindPicked).width(Length::Fill).style(theme);
+        chart_kind_column = chart_kind_column.push(chart_kind_picklist);
+
         chart_settings_row = chart_settings_row.push(coin_column);
         chart_settings_row = chart_settings_row.push(vs_currency_column);
         chart_settings_row = chart_settings_row.push(time_period_column);
+        chart_settings_row = chart_settings_row.push(chart_kind_column);
 
         main_column = main_column.push(chart_settings_row);
 
-        if let TimePeriod::Custom = self.time_period {
-            let mut dates_row = Row::new().spacing(5).width(Length::Shrink);
+        // The legend doubles as the list of series: clicking one lets the pick lists above change it.
+        let mut legend_row = Row::new().spacing(5).width(Length::Shrink);
+        self.series_button_states.resize_with(options.series.len(), Default::default);
+        for (index, (key, (select_button_state, remove_button_state))) in options.series.iter().zip(self.series_button_states.iter_mut()).enumerate() {
+            let label = if index == self.selected_series { format!("> {}", key) } else { key.to_string() };
+            let label = Text::new(label).color(crate::styling::series_color(index, graph_color));
+            legend_row = legend_row.push(Button::new(select_button_state, label).on_press(Message::SeriesSelected(index)).style(theme));
+            if options.series.len() > 1 {
+                legend_row = legend_row.push(Button::new(remove_button_state, Text::new("x")).on_press(Message::SeriesRemoved(index)).style(theme));
+            }
+        }
+        legend_row = legend_row.push(Button::new(&mut self.add_series_button_state, Text::new("+ Compare")).on_press(Message::SeriesAdded).style(theme));
+        main_column = main_column.push(legend_row);
 
-            let mut from_year_column = Column::new().spacing(5).width(Length::FillPortion(1));
-            from_year_column = from_year_column.push(Text::new("Year"));
-            let from_year_picklist = PickList::new(&mut self.date_from_year_picklist_state, &self.years, Some(self.date_from.year), Message::DateFromYearUpdated).width(Length::Fill).style(theme);
-            from_year_column = from_year_column.push(from_year_picklist);
-
-            let mut from_month_column = Column::new().spacing(5).width(Length::FillPortion(1));
-            from_month_column = from_month_column.push(Text::new("Month"));
-            let from_month_picklist = PickList::new(&mut self.date_from_month_picklist_state, &self.months, Some(self.date_from.month), Message::DateFromMonthUpdated).width(Length::Fill).style(theme);
-            from_month_column = from_month_column.push(from_month_picklist);
-            
-            let mut from_day_column = Column::new().spacing(5).width(Length::FillPortion(1));
-            from_day_column = from_day_column.push(Text::new("Day"));
-            let from_day_picklist = PickList::new(&mut self.date_from_day_picklist_state, &self.days, Some(self.date_from.day), Message::DateFromDayUpdated).width(Length::Fill).style(theme);
-            from_day_column = from_day_column.push(from_day_picklist);
-
-            let mut to_year_column = Column::new().spacing(5).width(Length::FillPortion(1));
-            to_year_column = to_year_column.push(Text::new("Year"));
-            let to_year_picklist = PickList::new(&mut self.date_to_year_picklist_state, &self.years, Some(self.date_to.year), Message::DateToYearUpdated).width(Length::Fill).style(theme);
-            to_year_column = to_year_column.push(to_year_picklist);
-
-            let mut to_month_column = Column::new().spacing(5).width(Length::FillPortion(1));
-            to_month_column = to_month_column.push(Text::new("Month"));
-            let to_month_picklist = PickList::new(&mut self.date_to_month_picklist_state, &self.months, Some(self.date_to.month), Message::DateToMonthUpdated).width(Length::Fill).style(theme);
-            to_month_column = to_month_column.push(to_month_picklist);
-            
-            let mut to_day_column = Column::new().spacing(5).width(Length::FillPortion(1));
-            to_day_column = to_day_column.push(Text::new("Day"));
-            let to_day_picklist = PickList::new(&mut self.date_to_day_picklist_state, &self.days, Some(self.date_to.day), Message::DateToDayUpdated).width(Length::Fill).style(theme);
-            to_day_column = to_day_column.push(to_day_picklist);
-    
-            dates_row = dates_row.push(Text::new("From:").width(Length::Shrink));
-            dates_row = dates_row.push(from_year_column);
-            dates_row = dates_row.push(from_month_column);
-            dates_row = dates_row.push(from_day_column);
-            dates_row = dates_row.push(Text::new("To:").width(Length::Shrink));
-            dates_row = dates_row.push(to_year_column);
-            dates_row = dates_row.push(to_month_column);
-            dates_row = dates_row.push(to_day_column);
+        let mut display_row = Row::new().spacing(10).width(Length::Shrink);
+        display_row = display_row.push(Checkbox::new(options.normalized, "% change", Message::NormalizedToggled).style(theme));
+        display_row = display_row.push(Checkbox::new(options.log_scale, "log scale", Message::LogScaleToggled).style(theme));
+        display_row = display_row.push(Checkbox::new(options.show_volume, "volume", Message::VolumeToggled).style(theme));
+        display_row = display_row.push(Checkbox::new(options.show_market_cap, "market cap", Message::MarketCapToggled).style(theme));
+        display_row = display_row.push(Checkbox::new(options.auto_refresh, "auto-refresh", Message::AutoRefreshToggled).style(theme));
+        display_row = display_row.push(Checkbox::new(options.show_triggers, "triggers", Message::TriggersToggled).style(theme));
+        if self.chart_state.view.is_some() {
+            display_row = display_row.push(Button::new(&mut self.reset_zoom_button_state, Text::new("Reset zoom")).on_press(Message::ZoomReset).style(theme));
+        }
+        main_column = main_column.push(display_row);
 
-            main_column = main_column.push(dates_row);
+        let mut live_row = Row::new().spacing(10).width(Length::Shrink);
+        for (index, key) in options.series.iter().enumerate() {
+            if let Some(price) = self.live_prices.get(key) {
+                live_row = live_row.push(Text::new(format!("{}: {}", key, chart::format_price(*price))).color(crate::styling::series_color(index, graph_color)));
+            }
+        }
+        if let Some(last_updated) = self.last_updated {
+            live_row = live_row.push(Text::new(format!("Last updated at {}", last_updated.format("%H:%M:%S"))));
+        }
+        if let Some(ref err) = self.refresh_error {
+            live_row = live_row.push(Text::new(err.clone()).color(Color::from_rgb8(0xD0, 0x3B, 0x3B)));
+        }
+        if let Some(ref err) = self.overlay_error {
+            live_row = live_row.push(Text::new(err.clone()).color(Color::from_rgb8(0xD0, 0x3B, 0x3B)));
+        }
+        main_column = main_column.push(live_row);
+
+        let mut indicators_row = Row::new().spacing(5).width(Length::Shrink);
+        indicators_row = indicators_row.push(Text::new("Indicators:"));
+        self.indicator_button_states.resize_with(options.indicators.len(), Default::default);
+        for (index, (indicator, button_state)) in options.indicators.iter().zip(self.indicator_button_states.iter_mut()).enumerate() {
+            indicators_row = indicators_row.push(Button::new(button_state, Text::new(format!("{} x", indicator))).on_press(Message::IndicatorRemoved(index)).style(theme));
+        }
+        indicators_row = indicators_row.push(PickList::new(&mut self.indicator_kind_picklist_state, &IndicatorKind::ALL[..], Some(self.picked_indicator_kind), Message::IndicatorKindPicked).style(theme));
+        indicators_row = indicators_row.push(TextInput::new(&mut self.indicator_window_input_state, "window", &self.indicator_window_value, Message::IndicatorWindowChanged).on_submit(Message::IndicatorAdded).width(Length::Units(60)).padding(5).style(theme));
+        indicators_row = indicators_row.push(Button::new(&mut self.add_indicator_button_state, Text::new("Add")).on_press(Message::IndicatorAdded).style(theme));
+        if let Some(ref err) = self.indicator_error {
+            indicators_row = indicators_row.push(Text::new(err.clone()).color(Color::from_rgb8(0xD0, 0x3B, 0x3B)));
+        }
+        main_column = main_column.push(indicators_row);
+
+        let mut export_row = Row::new().spacing(5).width(Length::Shrink);
+        export_row = export_row.push(TextInput::new(&mut self.export_path_input_state, "file", &self.export_path, Message::ExportPathChanged).width(Length::Units(360)).padding(5).style(theme));
+        export_row = export_row.push(PickList::new(&mut self.export_format_picklist_state, &ExportFormat::ALL[..], Some(self.export_format), Message::ExportFormatPicked).style(theme));
+        export_row = export_row.push(PickList::new(&mut self.timestamp_format_picklist_state, &TimestampFormat::ALL[..], Some(self.timestamp_format), Message::TimestampFormatPicked).style(theme));
+        export_row = export_row.push(PickList::new(&mut self.timezone_picklist_state, &Timezone::ALL[..], Some(self.timezone), Message::TimezonePicked).style(theme));
+        export_row = export_row.push(Button::new(&mut self.export_button_state, Text::new("Export")).on_press(Message::ExportClicked).style(theme));
+        main_column = main_column.push(export_row);
+
+        let mut image_row = Row::new().spacing(5).width(Length::Shrink);
+        image_row = image_row.push(TextInput::new(&mut self.image_path_input_state, "file", &self.image_path, Message::ImagePathChanged).width(Length::Units(360)).padding(5).style(theme));
+        image_row = image_row.push(PickList::new(&mut self.image_format_picklist_state, &ImageFormat::ALL[..], Some(self.image_format), Message::ImageFormatPicked).style(theme));
+        image_row = image_row.push(TextInput::new(&mut self.image_width_input_state, "width", &self.image_width_value, Message::ImageWidthChanged).width(Length::Units(60)).padding(5).style(theme));
+        image_row = image_row.push(Text::new("x"));
+        image_row = image_row.push(TextInput::new(&mut self.image_height_input_state, "height", &self.image_height_value, Message::ImageHeightChanged).width(Length::Units(60)).padding(5).style(theme));
+        image_row = image_row.push(Button::new(&mut self.export_image_button_state, Text::new("Export image")).on_press(Message::ImageExportClicked).style(theme));
+        if let Some(ref status) = self.export_status {
+            image_row = image_row.push(Text::new(status.clone()).size(16));
+        }
+        main_column = main_column.push(image_row);
+
+        if let Some(alert) = self.alert.as_mut() {
+            let mut alert_row = Row::new().spacing(5).width(Length::Shrink);
+            alert_row = alert_row.push(Text::new(format!("Alert for {} at", alert.key)));
+            alert_row = alert_row.push(TextInput::new(&mut alert.price_input_state, "price", &alert.price_value, Message::AlertPriceChanged).on_submit(Message::AlertSaved).width(Length::Units(120)).padding(5).style(theme));
+            alert_row = alert_row.push(Button::new(&mut alert.save_button_state, Text::new("Set alert")).on_press(Message::AlertSaved).style(theme));
+            alert_row = alert_row.push(Button::new(&mut alert.cancel_button_state, Text::new("Cancel")).on_press(Message::AlertCancelled).style(theme));
+            let live_price = self.live_prices.get(&alert.key).cloned();
+            if let Ok((_, Some(warning))) = crate::triggers_tab_gui::check_target(&alert.price_value, live_price) {
+                alert_row = alert_row.push(Text::new(warning).size(16).color(Color::from_rgb8(0xE0, 0x8E, 0x0B)));
+            }
+            if let Some(ref err) = alert.error {
+                alert_row = alert_row.push(Text::new(err.clone()).color(Color::from_rgb8(0xD0, 0x3B, 0x3B)));
+            }
+            main_column = main_column.push(Container::new(alert_row).padding(5).style(theme));
         }
 
-        match self.data {
-            Ok(Some(ref data)) => {
-                if data.is_empty() {
-                    main_column = main_column.push(Text::new("There is no data for this period of time!"));
-                } else {
-                    let settings = Settings {
-                        theme: theme.into(),
-                        title: Some(format!("{} to {} graph", self.picked_coin.raw.id, self.picked_currency.raw.name)),
-                        min_x_label_distance: DistanceValue::Fixed(160.0),
-                        ..Default::default()
-                    };
-                    let min_x_value = data.iter().map(|(d, _)| *d).min().unwrap();
-                    let max_x_value = data.iter().map(|(d, _)| *d).max().unwrap();
-                    let min_y_value = data.iter().map(|(_, p)| *p).min_by(|f1, f2| f1.total_cmp(f2)).unwrap();
-                    let max_y_value = data.iter().map(|(_, p)| *p).max_by(|f1, f2| f1.total_cmp(f2)).unwrap();
-                    let plot_settings = PlotSettings {
-                        theme: PlotThemeSettings {
-                            line_color: self.settings.read().unwrap().graph_color,
-                            point_color: self.settings.read().unwrap().graph_color,
-                        },
-                        point_size1: 4.0,
-                        point_size2: 5.5,
-                        point_size3: 7.0,
-                        ..Default::default()
-                    };
-                    let mut plot_data = Vec::new();
-                    plot_data.push((plot_settings, data.clone()));
-                    let chart = line::Chart::new(
-                        settings,
-                        min_x_value,
-                        max_x_value,
-                        min_y_value,
-                        max_y_value,
-                        plot_data
-                    );
-            
-                    let canvas = Canvas::new(chart).width(Length::Fill).height(Length::Fill);
-                    let container: Container<_> = Container::new(canvas)
-                        .width(Length::Fill)
-                        .height(Length::Fill)
-                        .center_x()
-                        .center_y();
-                    let container_elem: Element<_> = container.into();
-            
-                    main_column = main_column.push(container_elem.map(Message::ChartMessage));
+        if let TimePeriod::Custom = options.time_period {
+            let mut dates_row = Row::new().spacing(5).width(Length::Shrink);
+            dates_row = dates_row.push(Text::new("From:"));
+            dates_row = dates_row.push(TextInput::new(&mut self.date_from_input_state, "2021-01-01 or -90d", &self.date_from_value, Message::DateFromChanged).on_submit(Message::DatesSubmitted).width(Length::Units(160)).padding(5).style(theme));
+            dates_row = dates_row.push(Text::new("To:"));
+            dates_row = dates_row.push(TextInput::new(&mut self.date_to_input_state, "2021-12-31 12:00 or now", &self.date_to_value, Message::DateToChanged).on_submit(Message::DatesSubmitted).width(Length::Units(160)).padding(5).style(theme));
+            dates_row = dates_row.push(Button::new(&mut self.apply_dates_button_state, Text::new("Apply")).on_press(Message::DatesSubmitted).style(theme));
+            dates_row = dates_row.push(Text::new("UTC").size(16));
+            if let Some(ref err) = self.date_error {
+                dates_row = dates_row.push(Text::new(err.clone()).color(Color::from_rgb8(0xD0, 0x3B, 0x3B)));
+            } else if self.picked_start.is_some() {
+                dates_row = dates_row.push(Text::new("Pick the last day on the calendar").size(16));
+            }
+            main_column = main_column.push(dates_row);
+            if let Some(ref err) = self.first_point_error {
+                main_column = main_column.push(Text::new(err.clone()).size(16).color(Color::from_rgb8(0xD0, 0x3B, 0x3B)));
+            }
+
+            let month = self.calendar_month;
+            let previous_month = (month - Duration::days(1)).with_day(1).unwrap();
+            let next_month = (month + Duration::days(31)).with_day(1).unwrap();
+            let mut calendar_header = Row::new().spacing(5).width(Length::Shrink);
+            let mut previous_button = Button::new(&mut self.calendar_previous_button_state, Text::new("<")).style(theme);
+            if calendar_years.first().map_or(true, |first_year| previous_month.year() >= *first_year) {
+                previous_button = previous_button.on_press(Message::CalendarMonthChanged(previous_month));
+            }
+            calendar_header = calendar_header.push(previous_button);
+            calendar_header = calendar_header.push(Text::new(month.format("%B").to_string()).width(Length::Units(100)));
+            calendar_header = calendar_header.push(PickList::new(&mut self.calendar_year_picklist_state, calendar_years, Some(month.year()), move |year| Message::CalendarMonthChanged(month.with_year(year).unwrap_or(month))).style(theme));
+            let mut next_button = Button::new(&mut self.calendar_next_button_state, Text::new(">")).style(theme);
+            if next_month.year() <= Utc::now().year() {
+                next_button = next_button.on_press(Message::CalendarMonthChanged(next_month));
+            }
+            calendar_header = calendar_header.push(next_button);
+
+            // Six weeks from the Monday on or before the first of the month, so every month fits.
+            let first_shown = month - Duration::days(month.weekday().num_days_from_monday() as i64);
+            let range_start = self.picked_start.unwrap_or(options.date_from).date();
+            let range_end = if self.picked_start.is_some() { range_start } else { (options.date_to - Duration::seconds(1)).date() };
+            let mut calendar_column = Column::new().spacing(2).width(Length::Shrink).push(calendar_header);
+            let mut week_row = Row::new().spacing(2);
+            for weekday in ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"].iter() {
+                week_row = week_row.push(Text::new(*weekday).size(16).width(Length::Units(36)));
+            }
+            calendar_column = calendar_column.push(week_row);
+            self.calendar_day_button_states.resize_with(42, Default::default);
+            let mut week_row = Row::new().spacing(2);
+            for (index, button_state) in self.calendar_day_button_states.iter_mut().enumerate() {
+                let day = first_shown + Duration::days(index as i64);
+                let mut label = Text::new(day.day().to_string()).size(16);
+                if day >= range_start && day <= range_end {
+                    label = label.color(graph_color);
+                } else if day.month() != month.month() {
+                    label = label.color(Color::from_rgb8(0x80, 0x80, 0x80));
+                }
+                week_row = week_row.push(Button::new(button_state, label).width(Length::Units(36)).on_press(Message::CalendarDayPicked(day)).style(theme));
+                if index % 7 == 6 {
+                    calendar_column = calendar_column.push(week_row);
+                    week_row = Row::new().spacing(2);
                 }
             }
-            Ok(None) => {
-                main_column = main_column.push(Text::new("Loading data, please wait..."));
+            main_column = main_column.push(calendar_column);
+        }
+
+        if let Some(ref err) = self.settings_error {
+            main_column = main_column.push(Text::new(err.clone()).size(16).color(Color::from_rgb8(0xD0, 0x3B, 0x3B)));
+        }
+
+        if let Some(ref err) = self.data_error {
+            main_column = main_column.push(Text::new("Failed to load data! See the erorr below..."));
+            main_column = main_column.push(Text::new(err.clone()));
+            return main_column.into();
+        }
+
+        let mut loading = false;
+        for key in options.series.iter() {
+            match self.data.get(key) {
+                Some(Ok(data)) if !data.prices.is_empty() => {}
+                Some(Ok(_)) => {
+                    main_column = main_column.push(Text::new(format!("There is no {} data for this period of time!", key)));
+                }
+                Some(Err(err)) => {
+                    main_column = main_column.push(Text::new(format!("Failed to load {}: {}", key, err)));
+                }
+                None => {
+                    loading = true;
+                }
             }
-            Err(ref err) => {
-                main_column = main_column.push(Text::new("Failed to load data! See the erorr below..."));
-                main_column = main_column.push(Text::new(err.to_string()));
+        }
+
+        if loading {
+            main_column = main_column.push(Text::new("Loading data, please wait..."));
+        } else if let Some(canvas) = canvas {
+            let container_elem: Element<_> = Container::new(canvas)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .center_x()
+                .center_y()
+                .into();
+
+            let mut chart_row = Row::new().spacing(10).height(Length::Fill).push(container_elem);
+            if let Some(lines) = stats_lines {
+                let mut stats_column = Column::new().spacing(5).width(Length::Shrink);
+                for line in lines {
+                    stats_column = stats_column.push(Text::new(line).size(16));
+                }
+                stats_column = stats_column.push(Button::new(&mut self.copy_stats_button_state, Text::new("Copy")).on_press(Message::StatsCopied).style(theme));
+                chart_row = chart_row.push(stats_column);
             }
+            main_column = main_column.push(chart_row);
         }
 
         main_column.into()
     }
 }
 
-async fn load_data(id: String, vs_currency: String, from: u64, to: u64, timestamp: u64) -> Message {
+/// How far apart the time labels of the line chart are at least, in pixels.
+const MIN_X_LABEL_DISTANCE: f32 = 160.0;
+
+fn chart_title(options: &ChartOptions) -> String {
+    match options.series.as_slice() {
+        [key] => format!("{} to {} graph", key.coin, key.currency),
+        _ => format!("Comparison graph"),
+    }
+}
+
+/// The year of the oldest data on CoinGecko, used until the oldest point of a series is known.
+const FIRST_DATA_YEAR: i32 = 2013;
+
+/// CoinGecko picks how far apart the points are from the length of the requested range. Returns that
+/// spacing, and the longest range that still gets it, both in seconds.
+fn granularity(span: i64) -> (i64, i64) {
+    const DAY: i64 = 60 * 60 * 24;
+    if span <= DAY {
+        (60 * 5, DAY)
+    } else if span <= DAY * 90 {
+        (60 * 60, DAY * 90)
+    } else {
+        (DAY, i64::MAX)
+    }
+}
+
+async fn load_trigger_overlay() -> Result<(Vec<Trigger>, Vec<HistoryEntry>), String> {
+    let triggers = crate::trigger_store::load().await.map_err(|err| err.to_string())?;
+    let history = crate::history::load().await.map_err(|err| err.to_string())?;
+    Ok((triggers, history))
+}
+
+async fn load_first_point(key: SeriesKey) -> (SeriesKey, Result<Option<NaiveDateTime>, String>) {
+    let first_point = first_point(&key).await
+        .map(|first_point| first_point.map(|first_point| NaiveDateTime::from_timestamp(first_point, 0)))
+        .map_err(|err| err.to_string());
+    (key, first_point)
+}
+
+/// Asks for everything there is, which CoinGecko sends one point a day for. That is a large download
+/// for an answer that never changes, so the answer is kept in the store.
+async fn first_point(key: &SeriesKey) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+    if let Some(first_point) = trigger_store::first_point(key.coin.clone(), key.currency.clone()).await? {
+        return Ok(Some(first_point));
+    }
     let client = coingecko_requests::api_client::Client::new();
-    let result = client.market_chart(&id, &vs_currency, from, to)
+    let now = Utc::now().timestamp() as u64;
+    let coin_range = client.market_chart(&key.coin, &key.currency, 0, now).await.map_err(|err| err.to_string())?;
+    let first_point = coin_range.prices.first().map(|(timestamp, _)| *timestamp as i64 / 1000);
+    if let Some(first_point) = first_point {
+        // Without the cache the next session only has to download it again.
+        if let Err(err) = trigger_store::set_first_point(key.coin.clone(), key.currency.clone(), first_point).await {
+            println!("Failed to cache the oldest data of {}: {}", key, err);
+        }
+    }
+    Ok(first_point)
+}
+
+async fn load_live_price(key: SeriesKey) -> Message {
+    let result = trigger_store::current_price(key.coin.clone(), key.currency.clone()).await;
+    Message::LivePriceLoaded(key, result.map_err(|err| err.to_string()))
+}
+
+async fn load_data(key: SeriesKey, from: u64, to: u64, timestamp: u64) -> Message {
+    let client = coingecko_requests::api_client::Client::new();
+    let to_points = |values: Vec<(_, f64)>| values
+        .into_iter()
+        .map(|(timestamp, value)| (NaiveDateTime::from_timestamp(timestamp as i64 / 1000, 0), value))
+        .collect::<Vec<_>>();
+    let result = client.market_chart(&key.coin, &key.currency, from, to)
         .await
-        .map(|coin_range| coin_range.prices
-            .into_iter()
-            .map(|(timestamp, price)| (NaiveDateTime::from_timestamp(timestamp as i64 / 1000, 0), price))
-            .collect::<Vec<_>>());
+        .map(|coin_range| MarketData {
+            prices: to_points(coin_range.prices),
+            volumes: to_points(coin_range.total_volumes),
+            market_caps: to_points(coin_range.market_caps),
+        });
     match result {
         Ok(data) => {
-            Message::DataLoaded(data, timestamp)
+            Message::DataLoaded(key, data, timestamp)
         }
         Err(err) => {
-            Message::DataLoadFailed(err.to_string(), timestamp)
+            Message::DataLoadFailed(key, err.to_string(), timestamp)
         }
     }
-}
\ No newline at end of file
+}
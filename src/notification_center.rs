@@ -0,0 +1,212 @@
+use std::{collections::VecDeque, fmt::Display, path::PathBuf, sync::{Arc, RwLock}, time::Duration};
+
+use chrono::{Local, NaiveDateTime, TimeZone};
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use crate::trigger_engine::{Firing, Notifier};
+
+const MAX_ENTRIES: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NotificationKind {
+    Alert,
+    EngineError,
+    DataLoadFailed,
+}
+
+impl Display for NotificationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NotificationKind::Alert => { write!(f, "Alert") }
+            NotificationKind::EngineError => { write!(f, "Engine error") }
+            NotificationKind::DataLoadFailed => { write!(f, "Data load failed") }
+        }
+    }
+}
+
+/// Where the GUI should take the user when an entry is opened.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum NotificationTarget {
+    Chart { coin: String, currency: String },
+    Trigger(i64),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationEntry {
+    pub id: u64,
+    pub kind: NotificationKind,
+    pub title: String,
+    pub body: String,
+    pub created_at: NaiveDateTime,
+    pub read: bool,
+    pub target: Option<NotificationTarget>,
+}
+
+/// Kept in `jna_notifications.json` in the data directory, so that alerts missed while JNA was closed
+/// can still be found after a restart.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct NotificationCenter {
+    entries: VecDeque<NotificationEntry>,
+    next_id: u64,
+    /// Goes up with every change, so that the center is only saved when it changed.
+    #[serde(skip)]
+    revision: u64,
+    /// Told about every change once `save_on_change` is running.
+    #[serde(skip)]
+    saver: Option<mpsc::UnboundedSender<()>>,
+}
+
+fn notifications_file() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let project_dirs = ProjectDirs::from("org", "jna", "jna")
+        .ok_or::<Box<dyn std::error::Error>>(From::from("Failed to get project_dirs!"))?;
+    Ok(project_dirs.data_dir().join("jna_notifications.json"))
+}
+
+impl NotificationCenter {
+    /// Reads the saved notifications, or starts out empty when there are none yet. A file that cannot be
+    /// parsed is moved aside, so that the next save does not replace it.
+    pub async fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let notifications_file = notifications_file()?;
+        if !notifications_file.exists() {
+            return Ok(Default::default());
+        }
+        match serde_json::from_str(&tokio::fs::read_to_string(&notifications_file).await?) {
+            Ok(notifications) => Ok(notifications),
+            Err(err) => {
+                let broken_file = notifications_file.with_extension("json.broken");
+                tokio::fs::rename(&notifications_file, &broken_file).await?;
+                Err(From::from(format!("{} (moved to {})", err, broken_file.display())))
+            }
+        }
+    }
+
+    pub fn push(&mut self, kind: NotificationKind, title: String, body: String, target: Option<NotificationTarget>) {
+        self.entries.push_front(NotificationEntry {
+            id: self.next_id,
+            kind,
+            title,
+            body,
+            created_at: Local::now().naive_local(),
+            read: false,
+            target,
+        });
+        self.next_id += 1;
+        self.entries.truncate(MAX_ENTRIES);
+        self.changed();
+    }
+
+    /// Newest entries come first.
+    pub fn entries(&self) -> impl Iterator<Item = &NotificationEntry> {
+        self.entries.iter()
+    }
+
+    pub fn get(&self, id: u64) -> Option<&NotificationEntry> {
+        self.entries.iter().find(|entry| entry.id == id)
+    }
+
+    pub fn unread_count(&self) -> usize {
+        self.entries.iter().filter(|entry| !entry.read).count()
+    }
+
+    pub fn mark_read(&mut self, id: u64) {
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.id == id) {
+            entry.read = true;
+            self.changed();
+        }
+    }
+
+    pub fn mark_all_read(&mut self) {
+        for entry in self.entries.iter_mut() {
+            entry.read = true;
+        }
+        self.changed();
+    }
+
+    fn changed(&mut self) {
+        self.revision += 1;
+        if let Some(ref saver) = self.saver {
+            // The saver only stops with the runtime.
+            let _ = saver.send(());
+        }
+    }
+}
+
+/// Starts the task that saves the notifications after every change. It is the only one that writes the
+/// file, so the GUI and the trigger engine never save at the same time. Must run on the tokio runtime.
+pub fn save_on_change(notifications: Arc<RwLock<NotificationCenter>>) {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    // The loaded notifications are already saved.
+    let mut saved = {
+        let mut center = notifications.write().unwrap();
+        center.saver = Some(sender);
+        center.revision
+    };
+    tokio::spawn(async move {
+        while receiver.recv().await.is_some() {
+            // Changes that came in during a save are all covered by the next one.
+            let revision = notifications.read().unwrap().revision;
+            if revision == saved {
+                continue;
+            }
+            match save(notifications.clone()).await {
+                Ok(revision) => {
+                    saved = revision;
+                }
+                Err(err) => {
+                    println!("Failed to save the notifications: {}", err);
+                }
+            }
+        }
+    });
+}
+
+/// Writes the notifications to the data directory and returns the revision that was saved. The file is
+/// replaced in one go, so a crash halfway through leaves the previous version behind instead of a truncated one.
+async fn save(notifications: Arc<RwLock<NotificationCenter>>) -> Result<u64, Box<dyn std::error::Error>> {
+    let (content, revision) = {
+        let notifications = notifications.read().unwrap();
+        (serde_json::to_string(&*notifications)?, notifications.revision)
+    };
+    let notifications_file = notifications_file()?;
+    if let Some(data_dir) = notifications_file.parent() {
+        tokio::fs::create_dir_all(data_dir).await?;
+    }
+    let temp_file = notifications_file.with_extension("json.tmp");
+    tokio::fs::write(&temp_file, content).await?;
+    tokio::fs::rename(&temp_file, &notifications_file).await?;
+    Ok(revision)
+}
+
+/// Records every firing in the notification center, so that missed desktop popups can be found later.
+pub struct InAppNotifier {
+    pub notifications: Arc<RwLock<NotificationCenter>>,
+}
+
+impl Notifier for InAppNotifier {
+    fn name(&self) -> &'static str {
+        "in-app"
+    }
+
+    fn rate_limit(&self, _settings: &crate::settings::Settings) -> Duration {
+        Duration::from_secs(0)
+    }
+
+    fn notify(&self, firings: &[Firing]) -> Result<(), Box<dyn std::error::Error>> {
+        let mut notifications = self.notifications.write().unwrap();
+        for firing in firings {
+            let mut body = format!("Trigger #{} reached {} (target {}) at {}", firing.trigger.rowid, firing.price, firing.trigger.target_price, Local.from_utc_datetime(&firing.fired_at).format("%Y-%m-%d %H:%M"));
+            let annotations = firing.annotations();
+            if !annotations.is_empty() {
+                body = format!("{}\n{}", body, annotations);
+            }
+            notifications.push(
+                NotificationKind::Alert,
+                firing.title(),
+                body,
+                Some(NotificationTarget::Chart { coin: firing.coin.clone(), currency: firing.currency.clone() }));
+        }
+        Ok(())
+    }
+}
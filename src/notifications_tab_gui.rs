@@ -0,0 +1,117 @@
+use std::sync::{Arc, RwLock};
+
+use iced::{Button, Clipboard, Color, Column, Command, Length, Row, Scrollable, Text, button, scrollable};
+
+use crate::notification_center::{NotificationCenter, NotificationKind};
+
+pub struct Flags {
+    pub settings: Arc<RwLock<crate::settings::Settings>>,
+    pub notifications: Arc<RwLock<NotificationCenter>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    MarkReadClicked(u64),
+    MarkAllReadClicked,
+    /// Handled by the parent gui, which knows how to reach the other tabs.
+    OpenClicked(u64),
+}
+
+pub struct Gui {
+    settings: Arc<RwLock<crate::settings::Settings>>,
+    notifications: Arc<RwLock<NotificationCenter>>,
+    mark_all_read_button_state: button::State,
+    scrollable_state: scrollable::State,
+    mark_read_button_states: Vec<button::State>,
+    open_button_states: Vec<button::State>,
+}
+
+impl Gui {
+    pub fn new(flags: Flags) -> (Self, Command<Message>) {
+        (Self {
+            settings: flags.settings,
+            notifications: flags.notifications,
+            mark_all_read_button_state: Default::default(),
+            scrollable_state: Default::default(),
+            mark_read_button_states: Vec::new(),
+            open_button_states: Vec::new(),
+        }, Command::none())
+    }
+
+    pub fn update(&mut self, message: Message, _clipboard: &mut Clipboard) -> Command<Message> {
+        match message {
+            Message::MarkReadClicked(id) => {
+                self.notifications.write().unwrap().mark_read(id);
+            }
+            Message::MarkAllReadClicked => {
+                self.notifications.write().unwrap().mark_all_read();
+            }
+            Message::OpenClicked(id) => {
+                self.notifications.write().unwrap().mark_read(id);
+            }
+        }
+        Command::none()
+    }
+
+    pub fn view(&mut self) -> iced::Element<'_, Message> {
+        let lock = self.settings.read().unwrap();
+        let theme = lock.theme;
+        let notifications = self.notifications.read().unwrap();
+
+        let mut main_column = Column::new()
+            .spacing(5)
+            .width(Length::Fill)
+            .height(Length::Fill);
+
+        let mut header_row = Row::new()
+            .spacing(5)
+            .width(Length::Fill);
+        header_row = header_row.push(Text::new(format!("Notifications ({} unread)", notifications.unread_count())).width(Length::Fill));
+        header_row = header_row.push(Button::new(&mut self.mark_all_read_button_state, Text::new("Mark all as read")).on_press(Message::MarkAllReadClicked).style(theme));
+        main_column = main_column.push(header_row);
+
+        let entries = notifications.entries().collect::<Vec<_>>();
+        self.mark_read_button_states = vec![Default::default(); entries.len()];
+        self.open_button_states = vec![Default::default(); entries.len()];
+
+        let mut scrollable = Scrollable::new(&mut self.scrollable_state)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .style(theme);
+
+        if entries.is_empty() {
+            scrollable = scrollable.push(Text::new("Nothing here yet. Alerts, engine errors and data load failures will show up in this list."));
+        }
+
+        for ((entry, mark_read_button_state), open_button_state) in entries.into_iter().zip(self.mark_read_button_states.iter_mut()).zip(self.open_button_states.iter_mut()) {
+            let kind_color = match entry.kind {
+                NotificationKind::Alert => Color::from_rgb8(0x2E, 0x9E, 0x44),
+                NotificationKind::EngineError | NotificationKind::DataLoadFailed => Color::from_rgb8(0xD0, 0x3B, 0x3B),
+            };
+            let mut text_column = Column::new()
+                .spacing(2)
+                .width(Length::Fill);
+            let title = if entry.read { entry.title.clone() } else { format!("* {}", entry.title) };
+            text_column = text_column.push(Text::new(format!("[{}] {}", entry.kind, title)).color(kind_color));
+            text_column = text_column.push(Text::new(entry.body.clone()).size(16));
+            text_column = text_column.push(Text::new(entry.created_at.format("%Y-%m-%d %H:%M:%S").to_string()).size(14));
+
+            let mut entry_row = Row::new()
+                .padding(5)
+                .spacing(5)
+                .width(Length::Fill);
+            entry_row = entry_row.push(text_column);
+            if entry.target.is_some() {
+                entry_row = entry_row.push(Button::new(open_button_state, Text::new("Open")).on_press(Message::OpenClicked(entry.id)).style(theme));
+            }
+            if !entry.read {
+                entry_row = entry_row.push(Button::new(mark_read_button_state, Text::new("Mark read")).on_press(Message::MarkReadClicked(entry.id)).style(theme));
+            }
+            scrollable = scrollable.push(entry_row);
+        }
+
+        main_column = main_column.push(scrollable);
+
+        main_column.into()
+    }
+}
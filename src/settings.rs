@@ -1,9 +1,10 @@
-use std::{io::Write, fs::OpenOptions, path::PathBuf};
+use std::{io::{ErrorKind, Write}, fs::OpenOptions, path::PathBuf};
 
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Timelike};
 use iced::Color;
 use tokio::{io::{AsyncRead, AsyncReadExt}};
 
-use crate::styling::{self, Theme};
+use crate::{chart_options::{ChartKind, ChartOptions, Indicator, IndicatorKind, SeriesKey, TimePeriod}, styling::{self, Theme}, trigger_list::{Direction, ListOptions, SortKey}, trigger_meta::TriggerState};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Settings {
@@ -11,7 +12,12 @@ pub struct Settings {
     pub theme: styling::Theme,
     pub show_all_coins: bool,
     pub show_all_currencies: bool,
-    pub graph_color: Color
+    pub graph_color: Color,
+    pub aggregation_window: u32,
+    pub notification_rate_limit: u32,
+    pub trigger_list: ListOptions,
+    pub triggers_file: Option<PathBuf>,
+    pub chart: ChartOptions,
 }
 
 impl Default for Settings {
@@ -21,7 +27,12 @@ impl Default for Settings {
             theme: Default::default(),
             show_all_coins: false,
             show_all_currencies: false,
-            graph_color: Color::from_rgb8(0, 200, 0)
+            graph_color: Color::from_rgb8(0, 200, 0),
+            aggregation_window: 0,
+            notification_rate_limit: 0,
+            trigger_list: Default::default(),
+            triggers_file: None,
+            chart: Default::default(),
         }
     }
 }
@@ -47,12 +58,69 @@ impl Settings {
             b,
             a,
         };
+        let defaults = Self::default();
+        let aggregation_window = or_missing(input.read_u32().await, defaults.aggregation_window)?;
+        let notification_rate_limit = or_missing(input.read_u32().await, defaults.notification_rate_limit)?;
+        let trigger_list = ListOptions {
+            sort: SortKey::from_discriminant(or_missing(input.read_u8().await, 0)?).unwrap_or_default(),
+            descending: or_missing(input.read_u8().await, 0)? == 1,
+            coin: Some(or_missing(read_string(input).await, String::new())?).filter(|coin| !coin.is_empty()),
+            currency: Some(or_missing(read_string(input).await, String::new())?).filter(|currency| !currency.is_empty()),
+            direction: match or_missing(input.read_u8().await, 0)? {
+                1 => Some(Direction::Up),
+                2 => Some(Direction::Down),
+                _ => None
+            },
+            state: or_missing(input.read_u8().await, 0)?.checked_sub(1).and_then(TriggerState::from_discriminant),
+            search: or_missing(read_string(input).await, String::new())?,
+        };
+        let triggers_file = Some(or_missing(read_string(input).await, String::new())?)
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from);
+        let mut series = Vec::new();
+        for _ in 0..or_missing(input.read_u32().await, 0)? {
+            let key = SeriesKey {
+                coin: read_string(input).await?,
+                currency: read_string(input).await?,
+            };
+            // A pair is only drawn once, even if an older version saved it twice.
+            if !series.contains(&key) {
+                series.push(key);
+            }
+        }
+        let chart = if series.is_empty() {
+            defaults.chart
+        } else {
+            let mut chart = ChartOptions {
+                series,
+                time_period: TimePeriod::from_discriminant(input.read_u8().await?).unwrap_or_default(),
+                date_from: read_date(input).await?,
+                date_to: read_date(input).await?,
+                normalized: or_missing(input.read_u8().await, 0)? == 1,
+                log_scale: or_missing(input.read_u8().await, 0)? == 1,
+                kind: ChartKind::from_discriminant(or_missing(input.read_u8().await, 0)?).unwrap_or_default(),
+                show_volume: or_missing(input.read_u8().await, 0)? == 1,
+                show_market_cap: or_missing(input.read_u8().await, 0)? == 1,
+                indicators: read_indicators(input).await?,
+                auto_refresh: or_missing(input.read_u8().await, 0)? == 1,
+                show_triggers: or_missing(input.read_u8().await, 0)? == 1,
+            };
+            // The times of day were added after the dates, so they come last.
+            chart.date_from += Duration::seconds(or_missing(input.read_u32().await, 0)? as i64);
+            chart.date_to += Duration::seconds(or_missing(input.read_u32().await, 0)? as i64);
+            chart
+        };
         Ok(Self {
             source,
             theme,
             show_all_coins,
             show_all_currencies,
-            graph_color
+            graph_color,
+            aggregation_window,
+            notification_rate_limit,
+            trigger_list,
+            triggers_file,
+            chart,
         })
     }
 
@@ -66,6 +134,96 @@ impl Settings {
         file.write(&self.graph_color.g.to_ne_bytes())?;
         file.write(&self.graph_color.b.to_ne_bytes())?;
         file.write(&self.graph_color.a.to_ne_bytes())?;
+        file.write(&self.aggregation_window.to_be_bytes())?;
+        file.write(&self.notification_rate_limit.to_be_bytes())?;
+        file.write(&[self.trigger_list.sort as u8])?;
+        file.write(&[self.trigger_list.descending as u8])?;
+        write_string(&mut file, self.trigger_list.coin.as_deref().unwrap_or_default())?;
+        write_string(&mut file, self.trigger_list.currency.as_deref().unwrap_or_default())?;
+        file.write(&[match self.trigger_list.direction {
+            None => 0,
+            Some(Direction::Up) => 1,
+            Some(Direction::Down) => 2,
+        }])?;
+        file.write(&[self.trigger_list.state.map(|state| state as u8 + 1).unwrap_or(0)])?;
+        write_string(&mut file, &self.trigger_list.search)?;
+        write_string(&mut file, &self.triggers_file.as_ref().map(|path| path.to_string_lossy().to_string()).unwrap_or_default())?;
+        file.write(&(self.chart.series.len() as u32).to_be_bytes())?;
+        for series in self.chart.series.iter() {
+            write_string(&mut file, &series.coin)?;
+            write_string(&mut file, &series.currency)?;
+        }
+        file.write(&[self.chart.time_period as u8])?;
+        write_date(&mut file, self.chart.date_from)?;
+        write_date(&mut file, self.chart.date_to)?;
+        file.write(&[self.chart.normalized as u8])?;
+        file.write(&[self.chart.log_scale as u8])?;
+        file.write(&[self.chart.kind as u8])?;
+        file.write(&[self.chart.show_volume as u8])?;
+        file.write(&[self.chart.show_market_cap as u8])?;
+        file.write(&(self.chart.indicators.len() as u32).to_be_bytes())?;
+        for indicator in self.chart.indicators.iter() {
+            file.write(&[indicator.kind as u8])?;
+            file.write(&indicator.window.to_be_bytes())?;
+        }
+        file.write(&[self.chart.auto_refresh as u8])?;
+        file.write(&[self.chart.show_triggers as u8])?;
+        file.write(&self.chart.date_from.num_seconds_from_midnight().to_be_bytes())?;
+        file.write(&self.chart.date_to.num_seconds_from_midnight().to_be_bytes())?;
         Ok(())
     }
+}
+
+async fn read_string<R: AsyncRead + Unpin>(input: &mut R) -> std::io::Result<String> {
+    let len = input.read_u32().await?;
+    let mut buff = vec![0; len as usize];
+    input.read_exact(&mut buff).await?;
+    String::from_utf8(buff).map_err(|err| std::io::Error::new(ErrorKind::InvalidData, err))
+}
+
+fn write_string<W: Write>(output: &mut W, value: &str) -> std::io::Result<()> {
+    output.write_all(&(value.len() as u32).to_be_bytes())?;
+    output.write_all(value.as_bytes())
+}
+
+/// Older versions let days like February 31 be saved, those fall back to the first of the month.
+async fn read_date<R: AsyncRead + Unpin>(input: &mut R) -> std::io::Result<NaiveDateTime> {
+    let year = input.read_u32().await? as i32;
+    let month = input.read_u32().await?;
+    let day = input.read_u32().await?;
+    let date = NaiveDate::from_ymd_opt(year, month, day)
+        .or_else(|| NaiveDate::from_ymd_opt(year, month, 1))
+        .unwrap_or_else(|| NaiveDate::from_ymd(1970, 1, 1));
+    Ok(date.and_hms(0, 0, 0))
+}
+
+/// Only the date is written here, the time of day is appended at the end of the file.
+fn write_date<W: Write>(output: &mut W, date: NaiveDateTime) -> std::io::Result<()> {
+    output.write_all(&(date.year() as u32).to_be_bytes())?;
+    output.write_all(&date.month().to_be_bytes())?;
+    output.write_all(&date.day().to_be_bytes())
+}
+
+async fn read_indicators<R: AsyncRead + Unpin>(input: &mut R) -> std::io::Result<Vec<Indicator>> {
+    let mut indicators = Vec::new();
+    for _ in 0..or_missing(input.read_u32().await, 0)? {
+        let kind = input.read_u8().await?;
+        let window = input.read_u32().await?;
+        if let Some(kind) = IndicatorKind::from_discriminant(kind) {
+            indicators.push(Indicator {
+                kind,
+                window,
+            });
+        }
+    }
+    Ok(indicators)
+}
+
+/// Fields appended to the config format after its first release are read through
+/// this, so that config files written by older versions still load with defaults.
+fn or_missing<T>(result: std::io::Result<T>, default: T) -> std::io::Result<T> {
+    match result {
+        Err(err) if err.kind() == ErrorKind::UnexpectedEof => Ok(default),
+        result => result,
+    }
 }
\ No newline at end of file
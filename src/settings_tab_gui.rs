@@ -1,6 +1,6 @@
-use std::sync::{Arc, RwLock};
+use std::{path::PathBuf, sync::{Arc, RwLock}};
 
-use iced::{Button, Checkbox, Clipboard, Column, Command, Container, Length, PickList, Row, Slider, Text, button, pick_list, slider};
+use iced::{Button, Checkbox, Clipboard, Column, Command, Container, Length, PickList, Row, Slider, Text, TextInput, button, pick_list, slider, text_input};
 
 use crate::styling::Theme;
 
@@ -17,6 +17,10 @@ pub enum Message {
     GreenChanged(u8),
     BlueChanged(u8),
     AlphaChanged(u8),
+    AggregationWindowChanged(u32),
+    NotificationRateLimitChanged(u32),
+    TriggersFileInputChanged(String),
+    TriggersFileSubmitted,
 }
 
 #[derive(Default)]
@@ -27,10 +31,19 @@ pub struct Gui {
     green_slider: slider::State,
     blue_slider: slider::State,
     alpha_slider: slider::State,
+    aggregation_window_slider: slider::State,
+    notification_rate_limit_slider: slider::State,
+    triggers_file_value: String,
+    triggers_file_input: text_input::State,
+    triggers_file_button: button::State,
 }
 
 impl Gui {
     pub fn new(flags: Flags) -> (Self, Command<Message>) {
+        let triggers_file_value = flags.settings.read().unwrap().triggers_file
+            .as_ref()
+            .map(|path| path.to_string_lossy().to_string())
+            .unwrap_or_default();
         (Self {
             settings: flags.settings,
             theme_pick_list: Default::default(),
@@ -38,6 +51,11 @@ impl Gui {
             green_slider: Default::default(),
             blue_slider: Default::default(),
             alpha_slider: Default::default(),
+            aggregation_window_slider: Default::default(),
+            notification_rate_limit_slider: Default::default(),
+            triggers_file_value,
+            triggers_file_input: Default::default(),
+            triggers_file_button: Default::default(),
         }, Command::none())
     }
 
@@ -71,6 +89,22 @@ impl Gui {
                 self.settings.write().unwrap().graph_color.a = alpha as f32 / 255.0;
                 self.settings.read().unwrap().save().unwrap();
             }
+            Message::AggregationWindowChanged(seconds) => {
+                self.settings.write().unwrap().aggregation_window = seconds;
+                self.settings.read().unwrap().save().unwrap();
+            }
+            Message::NotificationRateLimitChanged(seconds) => {
+                self.settings.write().unwrap().notification_rate_limit = seconds;
+                self.settings.read().unwrap().save().unwrap();
+            }
+            Message::TriggersFileInputChanged(value) => {
+                self.triggers_file_value = value;
+            }
+            Message::TriggersFileSubmitted => {
+                let value = self.triggers_file_value.trim();
+                self.settings.write().unwrap().triggers_file = if value.is_empty() { None } else { Some(PathBuf::from(value)) };
+                self.settings.read().unwrap().save().unwrap();
+            }
         }
         Command::none()
     }
@@ -155,6 +189,43 @@ impl Gui {
         column = column.push(graph_color_blue_row);
         column = column.push(graph_color_alpha_row);
 
+        let mut aggregation_window_row = Row::new()
+            .padding(5)
+            .spacing(5)
+            .width(Length::Fill)
+            .height(Length::Shrink);
+
+        aggregation_window_row = aggregation_window_row.push(Text::new("Group alerts within").width(Length::Units(200)));
+        aggregation_window_row = aggregation_window_row.push(Slider::new(&mut self.aggregation_window_slider, 0..=600, lock.aggregation_window, Message::AggregationWindowChanged).step(30).width(Length::Units(256)).style(theme));
+        aggregation_window_row = aggregation_window_row.push(Text::new(if lock.aggregation_window == 0 { format!("one check") } else { format!("{} s", lock.aggregation_window) }));
+
+        column = column.push(aggregation_window_row);
+
+        let mut notification_rate_limit_row = Row::new()
+            .padding(5)
+            .spacing(5)
+            .width(Length::Fill)
+            .height(Length::Shrink);
+
+        notification_rate_limit_row = notification_rate_limit_row.push(Text::new("At most one alert every").width(Length::Units(200)));
+        notification_rate_limit_row = notification_rate_limit_row.push(Slider::new(&mut self.notification_rate_limit_slider, 0..=600, lock.notification_rate_limit, Message::NotificationRateLimitChanged).step(30).width(Length::Units(256)).style(theme));
+        notification_rate_limit_row = notification_rate_limit_row.push(Text::new(format!("{} s", lock.notification_rate_limit)));
+
+        column = column.push(notification_rate_limit_row);
+
+        let mut triggers_file_row = Row::new()
+            .padding(5)
+            .spacing(5)
+            .width(Length::Fill)
+            .height(Length::Shrink);
+
+        let applied = lock.triggers_file.as_ref().map(|path| path.to_string_lossy().to_string()).unwrap_or_default() == self.triggers_file_value.trim();
+        triggers_file_row = triggers_file_row.push(Text::new("Triggers file").width(Length::Units(200)));
+        triggers_file_row = triggers_file_row.push(TextInput::new(&mut self.triggers_file_input, "path to triggers.toml (empty to disable)", &self.triggers_file_value, Message::TriggersFileInputChanged).on_submit(Message::TriggersFileSubmitted).width(Length::Units(400)).padding(5).style(theme));
+        triggers_file_row = triggers_file_row.push(Button::new(&mut self.triggers_file_button, Text::new(if applied { "Applied" } else { "Apply" })).on_press(Message::TriggersFileSubmitted).style(theme));
+
+        column = column.push(triggers_file_row);
+
         Container::new(column)
             .width(Length::Fill)
             .height(Length::Fill)
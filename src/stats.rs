@@ -0,0 +1,150 @@
+//! Summary statistics of prices ordered by time, for the stats panel of the Main tab.
+
+use chrono::NaiveDateTime;
+
+const SECONDS_PER_YEAR: f64 = 60.0 * 60.0 * 24.0 * 365.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Summary {
+    pub open: (NaiveDateTime, f64),
+    pub close: (NaiveDateTime, f64),
+    pub high: (NaiveDateTime, f64),
+    pub low: (NaiveDateTime, f64),
+    pub change: f64,
+    /// None when the open price is zero.
+    pub change_percent: Option<f64>,
+    pub mean: f64,
+    /// The sample standard deviation of the returns from one point to the next, in percent.
+    pub return_stddev: Option<f64>,
+    /// The standard deviation of the returns, each scaled to a year by the time between its points, in percent.
+    pub annualized_volatility: Option<f64>,
+    /// The largest fall from a high to a later low, in percent of the high.
+    pub max_drawdown: f64,
+}
+
+/// Returns None for no points.
+pub fn summarize(points: &[(NaiveDateTime, f64)]) -> Option<Summary> {
+    let open = *points.first()?;
+    let close = *points.last()?;
+    let mut high = open;
+    let mut low = open;
+    let mut peak = open.1;
+    let mut max_drawdown: f64 = 0.0;
+    for point in points.iter() {
+        if point.1 > high.1 {
+            high = *point;
+        }
+        if point.1 < low.1 {
+            low = *point;
+        }
+        peak = peak.max(point.1);
+        if peak > 0.0 {
+            max_drawdown = max_drawdown.max((peak - point.1) / peak * 100.0);
+        }
+    }
+    let mean = points.iter().map(|(_, price)| price).sum::<f64>() / points.len() as f64;
+
+    // Every return with the seconds between its two points.
+    let returns: Vec<(f64, f64)> = points.windows(2)
+        .filter(|pair| pair[0].1 != 0.0)
+        .map(|pair| (pair[1].1 / pair[0].1 - 1.0, (pair[1].0.timestamp() - pair[0].0.timestamp()) as f64))
+        .collect();
+    let return_stddev = sample_stddev(returns.iter().map(|(value, _)| *value).collect());
+    // Scaling every return to one second first keeps the volatility right when the points are not evenly spaced.
+    let annualized_volatility = sample_stddev(returns.iter()
+            .filter(|(_, seconds)| *seconds > 0.0)
+            .map(|(value, seconds)| value / seconds.sqrt())
+            .collect())
+        .map(|stddev| stddev * SECONDS_PER_YEAR.sqrt() * 100.0);
+
+    Some(Summary {
+        open,
+        close,
+        high,
+        low,
+        change: close.1 - open.1,
+        change_percent: Some(open.1).filter(|open| *open != 0.0).map(|open| (close.1 - open) / open * 100.0),
+        mean,
+        return_stddev: return_stddev.map(|stddev| stddev * 100.0),
+        annualized_volatility,
+        max_drawdown,
+    })
+}
+
+/// None for fewer than two values.
+fn sample_stddev(values: Vec<f64>) -> Option<f64> {
+    if values.len() < 2 {
+        return None;
+    }
+    let count = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / count;
+    let variance = values.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / (count - 1.0);
+    Some(variance.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 60 * 60 * 24;
+
+    /// Prices at the given offsets in days.
+    fn points(prices: &[(i64, f64)]) -> Vec<(NaiveDateTime, f64)> {
+        prices.iter().map(|(day, price)| (NaiveDateTime::from_timestamp(day * DAY, 0), *price)).collect()
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn prices_and_drawdown() {
+        let prices = points(&[(0, 100.0), (1, 120.0), (2, 90.0), (3, 130.0), (4, 65.0)]);
+        let summary = summarize(&prices).unwrap();
+        assert_eq!(summary.open, prices[0]);
+        assert_eq!(summary.close, prices[4]);
+        assert_eq!(summary.high, prices[3]);
+        assert_eq!(summary.low, prices[4]);
+        assert_close(summary.change, -35.0);
+        assert_close(summary.change_percent.unwrap(), -35.0);
+        assert_close(summary.mean, 101.0);
+        // 120 to 90 is 25%, but 130 to 65 is 50%.
+        assert_close(summary.max_drawdown, 50.0);
+    }
+
+    #[test]
+    fn rising_prices_have_no_drawdown() {
+        assert_close(summarize(&points(&[(0, 1.0), (1, 2.0), (2, 3.0)])).unwrap().max_drawdown, 0.0);
+    }
+
+    #[test]
+    fn return_stddev_and_volatility() {
+        // Returns of +10% and -10%, a day apart.
+        let summary = summarize(&points(&[(0, 100.0), (1, 110.0), (2, 99.0)])).unwrap();
+        assert_close(summary.return_stddev.unwrap(), 0.02f64.sqrt() * 100.0);
+        assert_close(summary.annualized_volatility.unwrap(), 0.02f64.sqrt() * 365f64.sqrt() * 100.0);
+    }
+
+    #[test]
+    fn volatility_follows_the_spacing_of_each_return() {
+        // +20% over four days moves as much per day as -10% over one day.
+        let summary = summarize(&points(&[(0, 100.0), (4, 120.0), (5, 108.0)])).unwrap();
+        assert_close(summary.annualized_volatility.unwrap(), 0.02f64.sqrt() * 365f64.sqrt() * 100.0);
+    }
+
+    #[test]
+    fn too_few_points() {
+        assert_eq!(summarize(&[]), None);
+        let summary = summarize(&points(&[(0, 100.0), (1, 110.0)])).unwrap();
+        assert_eq!(summary.return_stddev, None);
+        assert_eq!(summary.annualized_volatility, None);
+    }
+
+    #[test]
+    fn zero_open_price() {
+        let summary = summarize(&points(&[(0, 0.0), (1, 10.0), (2, 20.0), (3, 10.0)])).unwrap();
+        assert_eq!(summary.change_percent, None);
+        // The return from zero is left out, which leaves +100% and -50%.
+        assert_close(summary.return_stddev.unwrap(), 1.125f64.sqrt() * 100.0);
+    }
+}
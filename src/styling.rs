@@ -39,6 +39,70 @@ impl Default for Theme {
     }
 }
 
+const PALETTE: [Color; 8] = [
+    Color::from_rgb(0.84, 0.23, 0.23),
+    Color::from_rgb(0.88, 0.56, 0.04),
+    Color::from_rgb(0.72, 0.65, 0.05),
+    Color::from_rgb(0.23, 0.65, 0.30),
+    Color::from_rgb(0.10, 0.62, 0.65),
+    Color::from_rgb(0.20, 0.45, 0.85),
+    Color::from_rgb(0.52, 0.33, 0.82),
+    Color::from_rgb(0.80, 0.30, 0.62),
+];
+
+/// Every label gets a colour from a fixed palette, derived from its text so it stays the same between runs.
+pub fn label_color(label: &str) -> Color {
+    let hash = label.bytes().fold(0u32, |hash, byte| hash.wrapping_mul(31).wrapping_add(byte as u32));
+    PALETTE[hash as usize % PALETTE.len()]
+}
+
+/// The colour of the chart series at `index`, after the first one which uses the graph colour.
+pub fn series_color(index: usize, graph_color: Color) -> Color {
+    match index {
+        0 => graph_color,
+        index => PALETTE[(index - 1) % PALETTE.len()],
+    }
+}
+
+/// The colours of the Main tab chart.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChartTheme {
+    pub background: Color,
+    pub plot_background: Color,
+    pub text: Color,
+    pub grid: Color,
+    /// Candles that closed higher than they opened.
+    pub up: Color,
+    pub down: Color,
+}
+
+impl From<Theme> for ChartTheme {
+    fn from(theme: Theme) -> Self {
+        match theme {
+            Theme::Default | Theme::Light => {
+                ChartTheme {
+                    background: Color::WHITE,
+                    plot_background: Color::from_rgb8(241, 241, 241),
+                    text: Color::BLACK,
+                    grid: Color::from_rgb8(211, 211, 211),
+                    up: Color::from_rgb8(0x26, 0xA6, 0x5B),
+                    down: Color::from_rgb8(0xD6, 0x3B, 0x3B),
+                }
+            }
+            Theme::Dark => {
+                ChartTheme {
+                    background: Color::from_rgb8(0x36, 0x39, 0x3F),
+                    plot_background: dark::SURFACE,
+                    text: Color::WHITE,
+                    grid: Color::from_rgb8(0x5A, 0x5E, 0x66),
+                    up: Color::from_rgb8(0x43, 0xD1, 0x7F),
+                    down: Color::from_rgb8(0xF0, 0x5D, 0x5D),
+                }
+            }
+        }
+    }
+}
+
 impl From<Theme> for ThemeSettings {
     fn from(theme: Theme) -> Self {
         match theme {
@@ -0,0 +1,413 @@
+use std::{collections::HashMap, hash::{Hash, Hasher}, sync::{Arc, RwLock}, time::{Duration, Instant}};
+
+use chrono::{Local, NaiveDateTime, Utc};
+use iced_native::{futures::{self, stream::{BoxStream, StreamExt}}, subscription::Recipe};
+use notify_rust::Notification;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::{notification_center::{NotificationCenter, NotificationKind, NotificationTarget}, trigger_meta::TriggerState, trigger_store::{self, Trigger}, triggers_file::{self, TriggersFileStatus}};
+
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone)]
+pub struct Firing {
+    pub trigger: Trigger,
+    pub coin: String,
+    pub currency: String,
+    pub price: f64,
+    /// In UTC.
+    pub fired_at: NaiveDateTime,
+}
+
+impl Firing {
+    pub fn title(&self) -> String {
+        match self.trigger.meta.group {
+            Some(ref group) => format!("[{}] {} => {}", group, self.coin.to_uppercase(), self.currency.to_uppercase()),
+            None => format!("{} => {}", self.coin.to_uppercase(), self.currency.to_uppercase()),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self.trigger.meta.note {
+            Some(ref note) => format!("{}: {} (target {}) - {}", self.title(), self.price, self.trigger.target_price, note),
+            None => format!("{}: {} (target {})", self.title(), self.price, self.trigger.target_price),
+        }
+    }
+
+    /// The labels and note of the trigger, one per line, or an empty string if it has neither.
+    pub fn annotations(&self) -> String {
+        let mut lines = Vec::new();
+        let meta = &self.trigger.meta;
+        if !meta.labels.is_empty() {
+            lines.push(format!("Labels: {}", meta.labels.join(", ")));
+        }
+        if let Some(ref note) = meta.note {
+            lines.push(format!("Note: {}", note));
+        }
+        lines.join("\n")
+    }
+}
+
+/// The latest price the engine has seen for every coin/currency pair that has a trigger.
+/// Keys are `(coin rowid, currency rowid)`.
+#[derive(Debug, Default)]
+pub struct PriceSnapshot {
+    prices: HashMap<(i64, i64), (f64, NaiveDateTime)>,
+}
+
+impl PriceSnapshot {
+    pub fn get(&self, coin_id: i64, currency_id: i64) -> Option<(f64, NaiveDateTime)> {
+        self.prices.get(&(coin_id, currency_id)).cloned()
+    }
+
+    fn set(&mut self, coin_id: i64, currency_id: i64, price: f64) {
+        self.prices.insert((coin_id, currency_id), (price, Local::now().naive_local()));
+    }
+}
+
+/// Something that can show a batch of firings to the user.
+/// Every notifier gets its own queue and rate limit in the engine.
+pub trait Notifier: Send {
+    fn name(&self) -> &'static str;
+
+    fn rate_limit(&self, settings: &crate::settings::Settings) -> Duration {
+        Duration::from_secs(settings.notification_rate_limit as u64)
+    }
+
+    fn notify(&self, firings: &[Firing]) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+pub struct DesktopNotifier;
+
+impl Notifier for DesktopNotifier {
+    fn name(&self) -> &'static str {
+        "desktop"
+    }
+
+    fn notify(&self, firings: &[Firing]) -> Result<(), Box<dyn std::error::Error>> {
+        let (summary, body) = match firings {
+            [firing] => {
+                let trigger = &firing.trigger;
+                let mut body = format!("Initial Price: {}\nTarget Price: {}\nCurrent Price: {}\nDifference: {}", trigger.initial_price as i64, trigger.target_price as i64, firing.price, (firing.price - trigger.initial_price).abs() as i64);
+                let annotations = firing.annotations();
+                if !annotations.is_empty() {
+                    body = format!("{}\n{}", body, annotations);
+                }
+                (firing.title(), body)
+            }
+            _ => {
+                (format!("{} triggers fired", firings.len()),
+                 firings.iter().map(Firing::describe).collect::<Vec<_>>().join("\n"))
+            }
+        };
+        Notification::new()
+            .appname("JNA Monitor")
+            .summary(&summary)
+            .body(&body)
+            .icon("D:/Projects/Organisation/mywork/JNA_Monitor/icon.png")
+            .show()?;
+        Ok(())
+    }
+}
+
+struct Channel {
+    notifier: Box<dyn Notifier>,
+    queued: Vec<Firing>,
+    last_sent: Option<Instant>,
+}
+
+pub struct Engine {
+    coins: Vec<coingecko_requests::data::Coin>,
+    currencies: Vec<coingecko_requests::data::VsCurrency>,
+    settings: Arc<RwLock<crate::settings::Settings>>,
+    notifications: Arc<RwLock<NotificationCenter>>,
+    prices: Arc<RwLock<PriceSnapshot>>,
+    triggers_file_status: Arc<RwLock<TriggersFileStatus>>,
+    triggers_file_watcher: triggers_file::Watcher,
+    channels: Vec<Channel>,
+    pending: Vec<Firing>,
+    pending_since: Option<Instant>,
+    last_error: Option<String>,
+    skipped: Vec<i64>,
+    /// Triggers whose firings were recorded and queued, but that could not be removed from the store.
+    /// They are removed again before the next check and never fire twice in the meantime.
+    unretired: Vec<i64>,
+    updates: broadcast::Sender<()>,
+}
+
+impl Engine {
+    /// `updates` gets a message after every check, which is when the notifications, prices and triggers file status may have changed.
+    pub fn new(coins: Vec<coingecko_requests::data::Coin>, currencies: Vec<coingecko_requests::data::VsCurrency>, settings: Arc<RwLock<crate::settings::Settings>>, notifications: Arc<RwLock<NotificationCenter>>, prices: Arc<RwLock<PriceSnapshot>>, triggers_file_status: Arc<RwLock<TriggersFileStatus>>, updates: broadcast::Sender<()>) -> Self {
+        Self {
+            coins,
+            currencies,
+            settings,
+            notifications,
+            prices,
+            triggers_file_status,
+            triggers_file_watcher: Default::default(),
+            channels: Vec::new(),
+            pending: Vec::new(),
+            pending_since: None,
+            last_error: None,
+            skipped: Vec::new(),
+            unretired: Vec::new(),
+            updates,
+        }
+    }
+
+    pub fn with_notifier<N: Notifier + 'static>(mut self, notifier: N) -> Self {
+        self.channels.push(Channel {
+            notifier: Box::new(notifier),
+            queued: Vec::new(),
+            last_sent: None,
+        });
+        self
+    }
+
+    pub async fn run(mut self) {
+        loop {
+            let triggers_file = self.settings.read().unwrap().triggers_file.clone();
+            if let Some(status) = self.triggers_file_watcher.poll(triggers_file, &self.coins, &self.currencies).await {
+                *self.triggers_file_status.write().unwrap() = status;
+            }
+            println!("Checking triggers");
+            let result = match self.check_triggers().await.map_err(|err| err.to_string()) {
+                Ok((firings, price_errors)) => {
+                    let retired = self.retire(firings).await;
+                    // A pair without a price only skips its own triggers, but the failure is still reported.
+                    retired.and_then(|_| match price_errors.into_iter().next() {
+                        Some(err) => Err(err),
+                        None => Ok(()),
+                    })
+                }
+                Err(err) => Err(err),
+            };
+            match result {
+                Ok(()) => {
+                    self.last_error = None;
+                }
+                Err(err) => {
+                    println!("Failed to check triggers: {}", err);
+                    // A failing connection would otherwise add the same entry every cycle.
+                    if self.last_error.as_ref() != Some(&err) {
+                        self.notifications.write().unwrap().push(NotificationKind::EngineError, format!("Failed to check triggers"), err.clone(), None);
+                        self.last_error = Some(err);
+                    }
+                }
+            }
+            self.dispatch();
+            // Nobody listens before the GUI has started.
+            let _ = self.updates.send(());
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    }
+
+    /// Returns the firings without removing their triggers, together with the pairs whose price could not be fetched.
+    async fn check_triggers(&mut self) -> Result<(Vec<Firing>, Vec<String>), Box<dyn std::error::Error>> {
+        let api_client = coingecko_requests::api_client::Client::new();
+        let client = coingecko_requests::caching_client::Client::new(api_client).await?;
+        let triggers = trigger_store::load().await?;
+        let now = Local::now().timestamp();
+        let mut firings = Vec::new();
+        let mut price_errors = Vec::new();
+        for trigger in triggers {
+            if trigger.meta.state(now) != TriggerState::Active {
+                continue;
+            }
+            let coin = self.coins.iter().find(|coin| coin.rowid == trigger.coin_id).map(|coin| coin.raw.id.clone());
+            let currency = self.currencies.iter().find(|currency| currency.rowid == trigger.currency_id).map(|currency| currency.raw.name.clone());
+            let (coin, currency) = match coin.zip(currency) {
+                Some(pair) => pair,
+                None => {
+                    if !self.skipped.contains(&trigger.rowid) {
+                        self.skipped.push(trigger.rowid);
+                        self.notifications.write().unwrap().push(
+                            NotificationKind::EngineError,
+                            format!("Trigger #{} skipped", trigger.rowid),
+                            format!("Unknown coin or currency (coin id {}, currency id {})", trigger.coin_id, trigger.currency_id),
+                            Some(NotificationTarget::Trigger(trigger.rowid)));
+                    }
+                    continue;
+                }
+            };
+            let increase = trigger.initial_price <= trigger.target_price;
+
+            let price = match client.price(&[coin.as_str()], &[currency.as_str()]).await {
+                Ok(data) => data.get(&coin).and_then(|prices| prices.get(&currency)).cloned()
+                    .ok_or_else(|| format!("No price for {} in {}", coin, currency)),
+                Err(err) => Err(format!("Failed to get the price of {} in {}: {}", coin, currency, err)),
+            };
+            let price = match price {
+                Ok(price) => price,
+                Err(err) => {
+                    if !price_errors.contains(&err) {
+                        price_errors.push(err);
+                    }
+                    continue;
+                }
+            };
+            self.prices.write().unwrap().set(trigger.coin_id, trigger.currency_id, price);
+            if (increase && price >= trigger.target_price) || (!increase && price <= trigger.target_price) {
+                firings.push(Firing {
+                    trigger,
+                    coin,
+                    currency,
+                    price,
+                    fired_at: Utc::now().naive_utc(),
+                });
+            }
+        }
+        Ok((firings, price_errors))
+    }
+
+    /// Records the firings in the history and only then removes their triggers. Firings that could not be
+    /// recorded keep their triggers, so they fire again on the next check instead of being lost. Recorded
+    /// firings whose triggers could not be removed are queued anyway, and their triggers are removed later.
+    async fn retire(&mut self, firings: Vec<Firing>) -> Result<(), String> {
+        let retried = if self.unretired.is_empty() {
+            Ok(())
+        } else {
+            trigger_store::retire(self.unretired.clone(), Utc::now().timestamp()).await
+                .map(|_| self.unretired.clear())
+                .map_err(|err| format!("Failed to remove fired triggers: {}", err))
+        };
+        let firings = self.new_firings(firings);
+        if firings.is_empty() {
+            return retried;
+        }
+        crate::history::append(&firings).await.map_err(|err| format!("Failed to record trigger history: {}", err))?;
+        let rowids = firings.iter().map(|firing| firing.trigger.rowid).collect();
+        let deleted = trigger_store::retire(rowids, Utc::now().timestamp()).await.map_err(|err| format!("Failed to remove fired triggers: {}", err));
+        self.recorded(firings, deleted).and(retried)
+    }
+
+    /// Leaves out the firings of triggers that already fired but are still in the store.
+    fn new_firings(&self, firings: Vec<Firing>) -> Vec<Firing> {
+        firings.into_iter().filter(|firing| !self.unretired.contains(&firing.trigger.rowid)).collect()
+    }
+
+    /// Queues firings that are in the history, and remembers their triggers if they could not be removed.
+    fn recorded(&mut self, firings: Vec<Firing>, deleted: Result<(), String>) -> Result<(), String> {
+        if deleted.is_err() {
+            self.unretired.extend(firings.iter().map(|firing| firing.trigger.rowid));
+        }
+        if self.pending.is_empty() {
+            self.pending_since = Some(Instant::now());
+        }
+        self.pending.extend(firings);
+        deleted
+    }
+
+    /// Hands the pending firings over to the notifiers once the aggregation window
+    /// has passed, then sends whatever each notifier's rate limit allows.
+    /// A window of zero groups the firings of a single check cycle.
+    fn dispatch(&mut self) {
+        let settings = self.settings.read().unwrap().clone();
+        let window = Duration::from_secs(settings.aggregation_window as u64);
+
+        let window_passed = self.pending_since
+            .map(|since| since.elapsed() >= window)
+            .unwrap_or(false);
+        if window_passed {
+            self.pending_since = None;
+            let firings = std::mem::take(&mut self.pending);
+            for channel in self.channels.iter_mut() {
+                channel.queued.extend(firings.iter().cloned());
+            }
+        }
+
+        for channel in self.channels.iter_mut() {
+            if channel.queued.is_empty() {
+                continue;
+            }
+            let rate_limit = channel.notifier.rate_limit(&settings);
+            let allowed = channel.last_sent
+                .map(|last_sent| last_sent.elapsed() >= rate_limit)
+                .unwrap_or(true);
+            if !allowed {
+                println!("Notifier '{}' is rate limited, {} firing(s) queued", channel.notifier.name(), channel.queued.len());
+                continue;
+            }
+            if let Err(err) = channel.notifier.notify(&channel.queued) {
+                println!("Notifier '{}' failed: {}", channel.notifier.name(), err);
+            }
+            channel.queued.clear();
+            channel.last_sent = Some(Instant::now());
+        }
+    }
+}
+
+/// A subscription that produces a message whenever the engine sends an update, so the GUI only has to
+/// redraw when there may be something new to show.
+pub fn updates(sender: &broadcast::Sender<()>) -> iced::Subscription<()> {
+    iced::Subscription::from_recipe(Updates(sender.clone()))
+}
+
+struct Updates(broadcast::Sender<()>);
+
+impl<H: Hasher, E> Recipe<H, E> for Updates {
+    type Output = ();
+
+    fn hash(&self, state: &mut H) {
+        std::any::TypeId::of::<Self>().hash(state);
+    }
+
+    fn stream(self: Box<Self>, _input: BoxStream<'static, E>) -> BoxStream<'static, Self::Output> {
+        futures::stream::unfold(self.0.subscribe(), |mut receiver| async move {
+            match receiver.recv().await {
+                // Missed updates only mean a redraw is due.
+                Ok(()) | Err(RecvError::Lagged(_)) => Some(((), receiver)),
+                Err(RecvError::Closed) => None,
+            }
+        }).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine() -> Engine {
+        Engine::new(Vec::new(), Vec::new(), Default::default(), Default::default(), Default::default(), Default::default(), broadcast::channel(1).0)
+    }
+
+    fn firing(rowid: i64) -> Firing {
+        Firing {
+            trigger: Trigger {
+                rowid,
+                coin_id: 1,
+                currency_id: 1,
+                initial_price: 100.0,
+                target_price: 200.0,
+                meta: Default::default(),
+            },
+            coin: "bitcoin".to_string(),
+            currency: "usd".to_string(),
+            price: 210.0,
+            fired_at: NaiveDateTime::from_timestamp(0, 0),
+        }
+    }
+
+    #[test]
+    fn firings_are_queued_once_when_their_triggers_cannot_be_removed() {
+        let mut engine = engine();
+        let result = engine.recorded(vec![firing(1), firing(2)], Err("disk full".to_string()));
+        assert_eq!(result, Err("disk full".to_string()));
+        assert_eq!(engine.pending.len(), 2);
+        assert_eq!(engine.unretired, vec![1, 2]);
+
+        // The triggers are still in the store, so the next check fires them again.
+        let firings = engine.new_firings(vec![firing(1), firing(2), firing(3)]);
+        assert_eq!(firings.iter().map(|firing| firing.trigger.rowid).collect::<Vec<_>>(), vec![3]);
+    }
+
+    #[test]
+    fn removed_triggers_are_not_remembered() {
+        let mut engine = engine();
+        assert_eq!(engine.recorded(vec![firing(1)], Ok(())), Ok(()));
+        assert_eq!(engine.pending.len(), 1);
+        assert!(engine.unretired.is_empty());
+        assert_eq!(engine.new_firings(vec![firing(1)]).len(), 1);
+    }
+}
@@ -0,0 +1,279 @@
+use std::{fmt::Display, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{trigger_engine::PriceSnapshot, trigger_meta::{self, TriggerMeta}, trigger_store::Trigger};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferFormat {
+    Csv,
+    Json,
+}
+
+impl Display for TransferFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransferFormat::Csv => { write!(f, "CSV") }
+            TransferFormat::Json => { write!(f, "JSON") }
+        }
+    }
+}
+
+impl TransferFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TransferFormat::Csv => "csv",
+            TransferFormat::Json => "json",
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "csv" => Some(TransferFormat::Csv),
+            "json" => Some(TransferFormat::Json),
+            _ => None
+        }
+    }
+}
+
+/// A trigger as it appears in an exported file. Coins and currencies are referenced by
+/// their CoinGecko id and name, because rowids differ between machines.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TriggerRecord {
+    pub coin: String,
+    pub currency: String,
+    #[serde(default)]
+    pub initial_price: Option<f64>,
+    pub target_price: f64,
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+    /// Comma separated, so that the record fits in a CSV row.
+    #[serde(default)]
+    pub labels: Option<String>,
+}
+
+impl TriggerRecord {
+    /// The metadata of a trigger imported from this record.
+    pub fn meta(&self) -> TriggerMeta {
+        TriggerMeta {
+            expires_at: self.expires_at,
+            group: self.group.clone(),
+            note: self.note.clone(),
+            labels: self.labels.as_deref().map(trigger_meta::parse_labels).unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordStatus {
+    /// Ready to be added, with the resolved coin and currency.
+    New(coingecko_requests::data::Coin, coingecko_requests::data::VsCurrency),
+    UnknownCoin,
+    UnknownCurrency,
+    /// Not a positive price, or an initial or current price it is already at.
+    InvalidTarget,
+    /// Same coin, currency and target as an existing trigger or an earlier record.
+    Duplicate,
+}
+
+impl Display for RecordStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordStatus::New(_, _) => { write!(f, "new") }
+            RecordStatus::UnknownCoin => { write!(f, "unknown coin") }
+            RecordStatus::UnknownCurrency => { write!(f, "unknown currency") }
+            RecordStatus::InvalidTarget => { write!(f, "invalid target") }
+            RecordStatus::Duplicate => { write!(f, "duplicate") }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportPreview {
+    pub rows: Vec<(TriggerRecord, RecordStatus)>,
+}
+
+impl ImportPreview {
+    /// `prices` are the ones the trigger engine last saw, for the records without an initial price.
+    pub fn new(records: Vec<TriggerRecord>, coins: &[coingecko_requests::data::Coin], currencies: &[coingecko_requests::data::VsCurrency], existing: &[Trigger], prices: &PriceSnapshot) -> Self {
+        let mut seen = existing.iter()
+            .map(|trigger| (trigger.coin_id, trigger.currency_id, trigger.target_price))
+            .collect::<Vec<_>>();
+        let rows = records.into_iter().map(|record| {
+            let coin = coins.iter().find(|coin| coin.raw.id == record.coin);
+            let currency = currencies.iter().find(|currency| currency.raw.name == record.currency);
+            let current_price = coin.zip(currency)
+                .and_then(|(coin, currency)| prices.get(coin.rowid, currency.rowid))
+                .map(|(price, _)| price);
+            let status = match (check(&record, coin.map(|coin| coin.rowid), currency.map(|currency| currency.rowid), current_price, &mut seen), coin, currency) {
+                (Ok(()), Some(coin), Some(currency)) => RecordStatus::New(coin.clone(), currency.clone()),
+                (Err(status), _, _) => status,
+                (Ok(()), None, _) => RecordStatus::UnknownCoin,
+                (Ok(()), _, None) => RecordStatus::UnknownCurrency,
+            };
+            (record, status)
+        }).collect();
+        Self {
+            rows,
+        }
+    }
+
+    pub fn new_count(&self) -> usize {
+        self.rows.iter().filter(|(_, status)| matches!(status, RecordStatus::New(_, _))).count()
+    }
+}
+
+/// Whether a record whose coin and currency resolved to the given rowids can be imported. Importable
+/// records are added to `seen`, so that a later copy of them is a duplicate.
+fn check(record: &TriggerRecord, coin_id: Option<i64>, currency_id: Option<i64>, current_price: Option<f64>, seen: &mut Vec<(i64, i64, f64)>) -> Result<(), RecordStatus> {
+    let coin_id = coin_id.ok_or(RecordStatus::UnknownCoin)?;
+    let currency_id = currency_id.ok_or(RecordStatus::UnknownCurrency)?;
+    // The same rules as for a target typed into the Triggers tab or written into the triggers file.
+    let target = record.target_price;
+    let initial_price_valid = record.initial_price.map_or(true, |price| price.is_finite() && price > 0.0);
+    if !target.is_finite() || target <= 0.0 || !initial_price_valid || record.initial_price.or(current_price) == Some(target) {
+        return Err(RecordStatus::InvalidTarget);
+    }
+    let key = (coin_id, currency_id, target);
+    if seen.contains(&key) {
+        return Err(RecordStatus::Duplicate);
+    }
+    seen.push(key);
+    Ok(())
+}
+
+pub fn records(triggers: &[Trigger], coins: &[coingecko_requests::data::Coin], currencies: &[coingecko_requests::data::VsCurrency]) -> Vec<TriggerRecord> {
+    triggers.iter().filter_map(|trigger| {
+        let coin = coins.iter().find(|coin| coin.rowid == trigger.coin_id)?;
+        let currency = currencies.iter().find(|currency| currency.rowid == trigger.currency_id)?;
+        let meta = trigger.meta.clone();
+        Some(TriggerRecord {
+            coin: coin.raw.id.clone(),
+            currency: currency.raw.name.clone(),
+            initial_price: Some(trigger.initial_price),
+            target_price: trigger.target_price,
+            expires_at: meta.expires_at,
+            group: meta.group,
+            note: meta.note,
+            labels: if meta.labels.is_empty() { None } else { Some(meta.labels.join(", ")) },
+        })
+    }).collect()
+}
+
+pub fn write(records: &[TriggerRecord], format: TransferFormat) -> Result<String, Box<dyn std::error::Error>> {
+    match format {
+        TransferFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            for record in records {
+                writer.serialize(record)?;
+            }
+            Ok(String::from_utf8(writer.into_inner()?)?)
+        }
+        TransferFormat::Json => {
+            Ok(serde_json::to_string_pretty(records)?)
+        }
+    }
+}
+
+pub fn read(content: &str, format: TransferFormat) -> Result<Vec<TriggerRecord>, Box<dyn std::error::Error>> {
+    match format {
+        TransferFormat::Csv => {
+            let mut reader = csv::Reader::from_reader(content.as_bytes());
+            let mut records = Vec::new();
+            for record in reader.deserialize() {
+                records.push(record?);
+            }
+            Ok(records)
+        }
+        TransferFormat::Json => {
+            Ok(serde_json::from_str(content)?)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(coin: &str, currency: &str, target_price: f64) -> TriggerRecord {
+        TriggerRecord {
+            coin: coin.to_string(),
+            currency: currency.to_string(),
+            initial_price: None,
+            target_price,
+            expires_at: None,
+            group: None,
+            note: None,
+            labels: None,
+        }
+    }
+
+    fn sample() -> Vec<TriggerRecord> {
+        vec![
+            TriggerRecord {
+                initial_price: Some(50000.0),
+                expires_at: Some(1767225600),
+                group: Some("swing levels".to_string()),
+                note: Some("breakout, above the \"previous\" high".to_string()),
+                labels: Some("swing, btc".to_string()),
+                ..record("bitcoin", "usd", 100000.0)
+            },
+            record("ethereum", "eur", 1500.5),
+        ]
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let records = sample();
+        assert_eq!(read(&write(&records, TransferFormat::Csv).unwrap(), TransferFormat::Csv).unwrap(), records);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let records = sample();
+        assert_eq!(read(&write(&records, TransferFormat::Json).unwrap(), TransferFormat::Json).unwrap(), records);
+    }
+
+    #[test]
+    fn labels_become_meta() {
+        assert_eq!(sample()[0].meta().labels, vec!["swing".to_string(), "btc".to_string()]);
+    }
+
+    #[test]
+    fn unknown_coins_and_currencies() {
+        let mut seen = Vec::new();
+        assert_eq!(check(&record("dogecoin", "usd", 1.0), None, Some(10), None, &mut seen), Err(RecordStatus::UnknownCoin));
+        assert_eq!(check(&record("bitcoin", "jpy", 1.0), Some(1), None, None, &mut seen), Err(RecordStatus::UnknownCurrency));
+        assert!(seen.is_empty());
+    }
+
+    #[test]
+    fn duplicates_of_existing_triggers_and_earlier_records() {
+        let mut seen = vec![(1, 10, 100000.0)];
+        assert_eq!(check(&record("bitcoin", "usd", 100000.0), Some(1), Some(10), None, &mut seen), Err(RecordStatus::Duplicate));
+        assert_eq!(check(&record("ethereum", "eur", 1500.0), Some(2), Some(11), None, &mut seen), Ok(()));
+        assert_eq!(check(&record("ethereum", "eur", 1500.0), Some(2), Some(11), None, &mut seen), Err(RecordStatus::Duplicate));
+        assert_eq!(check(&record("ethereum", "usd", 1500.0), Some(2), Some(10), None, &mut seen), Ok(()));
+    }
+
+    #[test]
+    fn invalid_targets() {
+        let mut seen = Vec::new();
+        for target in [0.0, -1.0, f64::NAN, f64::INFINITY].iter() {
+            assert_eq!(check(&record("bitcoin", "usd", *target), Some(1), Some(10), None, &mut seen), Err(RecordStatus::InvalidTarget));
+        }
+        let at_initial_price = TriggerRecord { initial_price: Some(100.0), ..record("bitcoin", "usd", 100.0) };
+        assert_eq!(check(&at_initial_price, Some(1), Some(10), None, &mut seen), Err(RecordStatus::InvalidTarget));
+        let negative_initial_price = TriggerRecord { initial_price: Some(-5.0), ..record("bitcoin", "usd", 100.0) };
+        assert_eq!(check(&negative_initial_price, Some(1), Some(10), None, &mut seen), Err(RecordStatus::InvalidTarget));
+        assert_eq!(check(&record("bitcoin", "usd", 100.0), Some(1), Some(10), Some(100.0), &mut seen), Err(RecordStatus::InvalidTarget));
+        // Invalid records do not make later ones duplicates.
+        assert!(seen.is_empty());
+        assert_eq!(check(&record("bitcoin", "usd", 100.0), Some(1), Some(10), Some(90.0), &mut seen), Ok(()));
+    }
+}
@@ -0,0 +1,164 @@
+use std::{cmp::Ordering, fmt::Display};
+
+use crate::trigger_meta::TriggerState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Created = 0,
+    Coin = 1,
+    Currency = 2,
+    Distance = 3,
+}
+
+impl Display for SortKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SortKey::Created => { write!(f, "Creation time") }
+            SortKey::Coin => { write!(f, "Coin") }
+            SortKey::Currency => { write!(f, "Currency") }
+            SortKey::Distance => { write!(f, "Distance to target") }
+        }
+    }
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 4] = [SortKey::Created, SortKey::Coin, SortKey::Currency, SortKey::Distance];
+
+    pub fn from_discriminant(discriminant: u8) -> Option<Self> {
+        match discriminant {
+            0 => Some(SortKey::Created),
+            1 => Some(SortKey::Coin),
+            2 => Some(SortKey::Currency),
+            3 => Some(SortKey::Distance),
+            _ => None
+        }
+    }
+}
+
+impl Default for SortKey {
+    fn default() -> Self {
+        SortKey::Created
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Direction::Up => { write!(f, "Up") }
+            Direction::Down => { write!(f, "Down") }
+        }
+    }
+}
+
+impl Direction {
+    pub const ALL: [Direction; 2] = [Direction::Up, Direction::Down];
+
+    pub fn of(initial_price: f64, target_price: f64) -> Self {
+        if initial_price <= target_price { Direction::Up } else { Direction::Down }
+    }
+}
+
+/// A pick list entry that either lets everything through or only one value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter<T> {
+    All,
+    Only(T),
+}
+
+impl<T: Display> Display for Filter<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Filter::All => { write!(f, "All") }
+            Filter::Only(value) => { write!(f, "{}", value) }
+        }
+    }
+}
+
+impl<T: PartialEq> Filter<T> {
+    pub fn from_option(value: Option<T>) -> Self {
+        match value {
+            Some(value) => Filter::Only(value),
+            None => Filter::All,
+        }
+    }
+
+    pub fn into_option(self) -> Option<T> {
+        match self {
+            Filter::All => None,
+            Filter::Only(value) => Some(value),
+        }
+    }
+
+    fn matches(&self, value: &T) -> bool {
+        match self {
+            Filter::All => true,
+            Filter::Only(only) => only == value,
+        }
+    }
+}
+
+/// The fields of a trigger the list can be sorted, filtered and searched by.
+#[derive(Debug, Clone)]
+pub struct ListItem {
+    pub rowid: i64,
+    pub coin: String,
+    pub currency: String,
+    pub group: Option<String>,
+    pub note: Option<String>,
+    pub labels: Vec<String>,
+    pub direction: Direction,
+    pub state: TriggerState,
+    pub created_at: Option<i64>,
+    pub distance: Option<f64>,
+}
+
+/// How the triggers tab orders and narrows its list. Persisted in the settings.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ListOptions {
+    pub sort: SortKey,
+    pub descending: bool,
+    pub coin: Option<String>,
+    pub currency: Option<String>,
+    pub direction: Option<Direction>,
+    pub state: Option<TriggerState>,
+    pub search: String,
+}
+
+impl ListOptions {
+    pub fn matches(&self, item: &ListItem) -> bool {
+        let search = self.search.trim().to_lowercase();
+        Filter::from_option(self.coin.clone()).matches(&item.coin)
+            && Filter::from_option(self.currency.clone()).matches(&item.currency)
+            && Filter::from_option(self.direction).matches(&item.direction)
+            && Filter::from_option(self.state).matches(&item.state)
+            && (search.is_empty()
+                || item.coin.to_lowercase().contains(&search)
+                || item.currency.to_lowercase().contains(&search)
+                || item.group.as_ref().map(|group| group.to_lowercase().contains(&search)).unwrap_or(false)
+                || item.note.as_ref().map(|note| note.to_lowercase().contains(&search)).unwrap_or(false)
+                || item.labels.iter().any(|label| label.to_lowercase().contains(&search))
+                || format!("#{}", item.rowid).contains(&search))
+    }
+
+    pub fn compare(&self, left: &ListItem, right: &ListItem) -> Ordering {
+        let ordering = match self.sort {
+            // Triggers created before JNA started recording creation times fall back to rowid order.
+            SortKey::Created => left.created_at.cmp(&right.created_at).then(left.rowid.cmp(&right.rowid)),
+            SortKey::Coin => left.coin.cmp(&right.coin),
+            SortKey::Currency => left.currency.cmp(&right.currency),
+            SortKey::Distance => {
+                let left_distance = left.distance.map(f64::abs).unwrap_or(f64::INFINITY);
+                let right_distance = right.distance.map(f64::abs).unwrap_or(f64::INFINITY);
+                left_distance.total_cmp(&right_distance)
+            }
+        };
+        let ordering = ordering.then(left.rowid.cmp(&right.rowid));
+        if self.descending { ordering.reverse() } else { ordering }
+    }
+}
@@ -0,0 +1,84 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerState {
+    Active,
+    Snoozed,
+    Expired,
+    Disabled,
+}
+
+impl Display for TriggerState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TriggerState::Active => { write!(f, "Active") }
+            TriggerState::Snoozed => { write!(f, "Snoozed") }
+            TriggerState::Expired => { write!(f, "Expired") }
+            TriggerState::Disabled => { write!(f, "Disabled") }
+        }
+    }
+}
+
+impl TriggerState {
+    pub const ALL: [TriggerState; 4] = [TriggerState::Active, TriggerState::Snoozed, TriggerState::Expired, TriggerState::Disabled];
+
+    pub fn from_discriminant(discriminant: u8) -> Option<Self> {
+        match discriminant {
+            0 => Some(TriggerState::Active),
+            1 => Some(TriggerState::Snoozed),
+            2 => Some(TriggerState::Expired),
+            3 => Some(TriggerState::Disabled),
+            _ => None
+        }
+    }
+}
+
+/// What JNA knows about a trigger beyond its coin, currency and prices.
+/// Timestamps are unix seconds.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TriggerMeta {
+    #[serde(default)]
+    pub created_at: Option<i64>,
+    #[serde(default)]
+    pub snoozed_until: Option<i64>,
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+    /// The id of the `triggers.toml` entry that owns this trigger. Such triggers are read-only in the GUI.
+    #[serde(default)]
+    pub file_key: Option<String>,
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub disabled: bool,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+}
+
+impl TriggerMeta {
+    pub fn state(&self, now: i64) -> TriggerState {
+        if self.disabled {
+            TriggerState::Disabled
+        } else if self.expires_at.map(|expires_at| expires_at <= now).unwrap_or(false) {
+            TriggerState::Expired
+        } else if self.snoozed_until.map(|snoozed_until| snoozed_until > now).unwrap_or(false) {
+            TriggerState::Snoozed
+        } else {
+            TriggerState::Active
+        }
+    }
+}
+
+/// Splits comma separated labels as they are typed on the triggers tab, e.g. `"swing, btc"`.
+pub fn parse_labels(labels: &str) -> Vec<String> {
+    let mut parsed = Vec::new();
+    for label in labels.split(',').map(str::trim).filter(|label| !label.is_empty()) {
+        if !parsed.iter().any(|parsed: &String| parsed == label) {
+            parsed.push(label.to_string());
+        }
+    }
+    parsed
+}
@@ -0,0 +1,387 @@
+use std::{collections::HashMap, path::{Path, PathBuf}, sync::atomic::{AtomicBool, Ordering}};
+
+use coingecko_requests::data::{Coin, VsCurrency};
+use directories::ProjectDirs;
+use rusqlite::{params, Connection, OptionalExtension, Transaction, TransactionBehavior};
+
+use crate::trigger_meta::TriggerMeta;
+
+/// Every entry moves the schema one version up. `PRAGMA user_version` holds the number of applied entries,
+/// so new entries must only ever be appended.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE triggers (
+        id INTEGER PRIMARY KEY,
+        coin_id INTEGER NOT NULL,
+        currency_id INTEGER NOT NULL,
+        initial_price REAL NOT NULL,
+        target_price REAL NOT NULL,
+        created_at INTEGER,
+        snoozed_until INTEGER,
+        expires_at INTEGER,
+        file_key TEXT,
+        group_name TEXT,
+        disabled INTEGER NOT NULL DEFAULT 0,
+        note TEXT
+    );
+    CREATE TABLE trigger_labels (
+        trigger_id INTEGER NOT NULL REFERENCES triggers(id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        label TEXT NOT NULL,
+        PRIMARY KEY (trigger_id, position)
+    );",
+    "CREATE TABLE fired_file_triggers (
+        file_key TEXT PRIMARY KEY,
+        coin_id INTEGER NOT NULL,
+        currency_id INTEGER NOT NULL,
+        initial_price REAL NOT NULL,
+        target_price REAL NOT NULL,
+        expires_at INTEGER,
+        fired_at INTEGER NOT NULL
+    );",
+    "CREATE TABLE first_points (
+        coin TEXT NOT NULL,
+        currency TEXT NOT NULL,
+        first_point INTEGER NOT NULL,
+        PRIMARY KEY (coin, currency)
+    );",
+];
+
+/// A trigger as JNA stores it. Coins and currencies are referenced by their rowids in the caching client.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trigger {
+    pub rowid: i64,
+    pub coin_id: i64,
+    pub currency_id: i64,
+    pub initial_price: f64,
+    pub target_price: f64,
+    pub meta: TriggerMeta,
+}
+
+fn data_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let project_dirs = ProjectDirs::from("org", "jna", "jna")
+        .ok_or::<Box<dyn std::error::Error>>(From::from("Failed to get project_dirs!"))?;
+    Ok(project_dirs.data_dir().to_path_buf())
+}
+
+/// The triggers from before JNA had its own store: the rows of the caching client, together with
+/// the metadata JNA used to keep next to them in `jna_triggers_meta.json`. Neither is changed.
+async fn legacy_triggers() -> Result<Vec<Trigger>, Box<dyn std::error::Error>> {
+    let api_client = coingecko_requests::api_client::Client::new();
+    let client = coingecko_requests::caching_client::Client::new(api_client).await?;
+    let triggers = client.get_all_triggers().await?;
+
+    let metas_file = data_dir()?.join("jna_triggers_meta.json");
+    let mut metas: HashMap<i64, TriggerMeta> = if metas_file.exists() {
+        serde_json::from_str(&tokio::fs::read_to_string(&metas_file).await?)?
+    } else {
+        HashMap::new()
+    };
+
+    Ok(triggers.into_iter().map(|trigger| Trigger {
+        rowid: trigger.rowid,
+        coin_id: trigger.coin_id,
+        currency_id: trigger.currency_id,
+        initial_price: trigger.initial_price,
+        target_price: trigger.target_price,
+        meta: metas.remove(&trigger.rowid).unwrap_or_default(),
+    }).collect())
+}
+
+pub struct TriggerStore {
+    connection: Connection,
+}
+
+impl TriggerStore {
+    /// Opens the store without migrating it. rusqlite blocks, so this is only called through `with_store`.
+    fn open(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch("PRAGMA foreign_keys = ON;")?;
+        Ok(Self {
+            connection,
+        })
+    }
+
+    fn version(&self) -> Result<usize, Box<dyn std::error::Error>> {
+        let version: i64 = self.connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        Ok(version as usize)
+    }
+
+    fn migrate(&mut self, legacy: Vec<Trigger>) -> Result<(), Box<dyn std::error::Error>> {
+        // The engine and the GUI may open the store at the same time, so the version is read
+        // again after taking the write lock.
+        let transaction = self.connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let version: i64 = transaction.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        let version = version as usize;
+        for migration in MIGRATIONS.iter().skip(version) {
+            transaction.execute_batch(migration)?;
+        }
+        if version == 0 {
+            for trigger in legacy.iter() {
+                insert(&transaction, trigger)?;
+            }
+        }
+        transaction.execute_batch(&format!("PRAGMA user_version = {};", MIGRATIONS.len()))?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    pub fn triggers(&self) -> Result<Vec<Trigger>, Box<dyn std::error::Error>> {
+        let mut statement = self.connection.prepare(
+            "SELECT id, coin_id, currency_id, initial_price, target_price, created_at, snoozed_until, expires_at, file_key, group_name, disabled, note
+             FROM triggers ORDER BY id")?;
+        let mut triggers = statement.query_map([], |row| {
+            Ok(Trigger {
+                rowid: row.get(0)?,
+                coin_id: row.get(1)?,
+                currency_id: row.get(2)?,
+                initial_price: row.get(3)?,
+                target_price: row.get(4)?,
+                meta: TriggerMeta {
+                    created_at: row.get(5)?,
+                    snoozed_until: row.get(6)?,
+                    expires_at: row.get(7)?,
+                    file_key: row.get(8)?,
+                    group: row.get(9)?,
+                    disabled: row.get(10)?,
+                    note: row.get(11)?,
+                    labels: Vec::new(),
+                },
+            })
+        })?.collect::<Result<Vec<_>, _>>()?;
+
+        let mut statement = self.connection.prepare("SELECT trigger_id, label FROM trigger_labels ORDER BY trigger_id, position")?;
+        let mut rows = statement.query([])?;
+        while let Some(row) = rows.next()? {
+            let trigger_id: i64 = row.get(0)?;
+            if let Some(trigger) = triggers.iter_mut().find(|trigger| trigger.rowid == trigger_id) {
+                trigger.meta.labels.push(row.get(1)?);
+            }
+        }
+
+        Ok(triggers)
+    }
+
+    /// Adds a trigger and returns its rowid. The rowid of `trigger` is ignored.
+    pub fn add(&mut self, trigger: &Trigger) -> Result<i64, Box<dyn std::error::Error>> {
+        let transaction = self.connection.transaction()?;
+        let rowid = insert(&transaction, &Trigger { rowid: 0, ..trigger.clone() })?;
+        transaction.commit()?;
+        Ok(rowid)
+    }
+
+    pub fn update_meta<F: FnMut(&mut TriggerMeta)>(&mut self, rowids: &[i64], mut f: F) -> Result<(), Box<dyn std::error::Error>> {
+        let mut triggers = self.triggers()?;
+        triggers.retain(|trigger| rowids.contains(&trigger.rowid));
+        let transaction = self.connection.transaction()?;
+        for trigger in triggers.iter_mut() {
+            f(&mut trigger.meta);
+            let meta = &trigger.meta;
+            transaction.execute(
+                "UPDATE triggers SET created_at = ?2, snoozed_until = ?3, expires_at = ?4, file_key = ?5, group_name = ?6, disabled = ?7, note = ?8 WHERE id = ?1",
+                params![trigger.rowid, meta.created_at, meta.snoozed_until, meta.expires_at, meta.file_key, meta.group, meta.disabled, meta.note])?;
+            transaction.execute("DELETE FROM trigger_labels WHERE trigger_id = ?1", params![trigger.rowid])?;
+            insert_labels(&transaction, trigger.rowid, &meta.labels)?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    pub fn delete(&mut self, rowids: &[i64]) -> Result<(), Box<dyn std::error::Error>> {
+        let transaction = self.connection.transaction()?;
+        for rowid in rowids {
+            transaction.execute("DELETE FROM triggers WHERE id = ?1", params![rowid])?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Deletes fired triggers. The ones owned by the triggers file are remembered, so that the file
+    /// does not bring them back until their entry changes.
+    pub fn retire(&mut self, rowids: &[i64], fired_at: i64) -> Result<(), Box<dyn std::error::Error>> {
+        let transaction = self.connection.transaction()?;
+        for rowid in rowids {
+            transaction.execute(
+                "INSERT OR REPLACE INTO fired_file_triggers (file_key, coin_id, currency_id, initial_price, target_price, expires_at, fired_at)
+                 SELECT file_key, coin_id, currency_id, initial_price, target_price, expires_at, ?2 FROM triggers WHERE id = ?1 AND file_key IS NOT NULL",
+                params![rowid, fired_at])?;
+            transaction.execute("DELETE FROM triggers WHERE id = ?1", params![rowid])?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// The triggers of the triggers file that fired, by their file key. Only the fields that
+    /// define a trigger are kept, and their rowids are 0.
+    pub fn fired_file_triggers(&self) -> Result<HashMap<String, Trigger>, Box<dyn std::error::Error>> {
+        let mut statement = self.connection.prepare(
+            "SELECT file_key, coin_id, currency_id, initial_price, target_price, expires_at FROM fired_file_triggers")?;
+        let fired = statement.query_map([], |row| {
+            let file_key: String = row.get(0)?;
+            Ok((file_key.clone(), Trigger {
+                rowid: 0,
+                coin_id: row.get(1)?,
+                currency_id: row.get(2)?,
+                initial_price: row.get(3)?,
+                target_price: row.get(4)?,
+                meta: TriggerMeta {
+                    expires_at: row.get(5)?,
+                    file_key: Some(file_key),
+                    ..Default::default()
+                },
+            }))
+        })?.collect::<Result<HashMap<_, _>, _>>()?;
+        Ok(fired)
+    }
+
+    pub fn forget_fired(&mut self, file_keys: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let transaction = self.connection.transaction()?;
+        for file_key in file_keys {
+            transaction.execute("DELETE FROM fired_file_triggers WHERE file_key = ?1", params![file_key])?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// The time of the oldest point CoinGecko has for a pair, in unix seconds, if it was looked up before.
+    pub fn first_point(&self, coin: &str, currency: &str) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+        Ok(self.connection.query_row(
+            "SELECT first_point FROM first_points WHERE coin = ?1 AND currency = ?2",
+            params![coin, currency],
+            |row| row.get(0)).optional()?)
+    }
+
+    pub fn set_first_point(&self, coin: &str, currency: &str, first_point: i64) -> Result<(), Box<dyn std::error::Error>> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO first_points (coin, currency, first_point) VALUES (?1, ?2, ?3)",
+            params![coin, currency, first_point])?;
+        Ok(())
+    }
+}
+
+/// Inserts with the given rowid, or with a fresh one when it is 0.
+fn insert(transaction: &Transaction, trigger: &Trigger) -> Result<i64, Box<dyn std::error::Error>> {
+    let meta = &trigger.meta;
+    let rowid = if trigger.rowid == 0 { None } else { Some(trigger.rowid) };
+    transaction.execute(
+        "INSERT INTO triggers (id, coin_id, currency_id, initial_price, target_price, created_at, snoozed_until, expires_at, file_key, group_name, disabled, note)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        params![rowid, trigger.coin_id, trigger.currency_id, trigger.initial_price, trigger.target_price, meta.created_at, meta.snoozed_until, meta.expires_at, meta.file_key, meta.group, meta.disabled, meta.note])?;
+    let rowid = transaction.last_insert_rowid();
+    insert_labels(transaction, rowid, &meta.labels)?;
+    Ok(rowid)
+}
+
+fn insert_labels(transaction: &Transaction, rowid: i64, labels: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    for (position, label) in labels.iter().enumerate() {
+        transaction.execute("INSERT INTO trigger_labels (trigger_id, position, label) VALUES (?1, ?2, ?3)", params![rowid, position as i64, label])?;
+    }
+    Ok(())
+}
+
+/// Set once the schema of the store is known to be up to date, so later calls do not check it again.
+static MIGRATED: AtomicBool = AtomicBool::new(false);
+
+/// Runs `f` on tokio's blocking threads, as rusqlite does not play well with the async runtime.
+async fn blocking<T, F>(f: F) -> Result<T, Box<dyn std::error::Error>>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, Box<dyn std::error::Error>> + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || f().map_err(|err| err.to_string()))
+        .await?
+        .map_err(From::from)
+}
+
+/// Opens the store in the data directory and runs `f` on it. The first call brings the schema up to date,
+/// and a store that is created from scratch starts out with the triggers of the caching client.
+async fn with_store<T, F>(f: F) -> Result<T, Box<dyn std::error::Error>>
+where
+    T: Send + 'static,
+    F: FnOnce(&mut TriggerStore) -> Result<T, Box<dyn std::error::Error>> + Send + 'static,
+{
+    let data_dir = data_dir()?;
+    tokio::fs::create_dir_all(&data_dir).await?;
+    let path = data_dir.join("jna_triggers.sqlite");
+    if !MIGRATED.load(Ordering::SeqCst) {
+        let version = {
+            let path = path.clone();
+            blocking(move || TriggerStore::open(&path)?.version()).await?
+        };
+        if version < MIGRATIONS.len() {
+            let legacy = if version == 0 { legacy_triggers().await? } else { Vec::new() };
+            let path = path.clone();
+            blocking(move || TriggerStore::open(&path)?.migrate(legacy)).await?;
+        }
+        MIGRATED.store(true, Ordering::SeqCst);
+    }
+    blocking(move || f(&mut TriggerStore::open(&path)?)).await
+}
+
+/// Creates the store or brings its schema up to date.
+pub async fn prepare() -> Result<(), Box<dyn std::error::Error>> {
+    with_store(|_| Ok(())).await
+}
+
+pub async fn current_price(coin: String, currency: String) -> Result<f64, Box<dyn std::error::Error>> {
+    let api_client = coingecko_requests::api_client::Client::new();
+    let client = coingecko_requests::caching_client::Client::new(api_client).await?;
+    let data = client.price(&[coin.as_str()], &[currency.as_str()]).await?;
+    data.get(&coin)
+        .and_then(|prices| prices.get(&currency))
+        .cloned()
+        .ok_or_else(|| From::from(format!("No price for {} in {}", coin, currency)))
+}
+
+pub async fn load() -> Result<Vec<Trigger>, Box<dyn std::error::Error>> {
+    with_store(|store| store.triggers()).await
+}
+
+/// Adds a trigger and returns its rowid. Without an `initial_price` the current price is fetched and used instead.
+/// `meta` becomes the metadata of the new trigger, with the creation time filled in.
+pub async fn add(coin: Coin, currency: VsCurrency, initial_price: Option<f64>, target_price: f64, meta: TriggerMeta) -> Result<i64, Box<dyn std::error::Error>> {
+    let initial_price = match initial_price {
+        Some(initial_price) => initial_price,
+        None => current_price(coin.raw.id.clone(), currency.raw.name.clone()).await?,
+    };
+
+    let trigger = Trigger {
+        rowid: 0,
+        coin_id: coin.rowid,
+        currency_id: currency.rowid,
+        initial_price,
+        target_price,
+        meta: TriggerMeta {
+            created_at: Some(chrono::Local::now().timestamp()),
+            ..meta
+        },
+    };
+    with_store(move |store| store.add(&trigger)).await
+}
+
+pub async fn update_meta<F: FnMut(&mut TriggerMeta) + Send + 'static>(rowids: Vec<i64>, f: F) -> Result<(), Box<dyn std::error::Error>> {
+    with_store(move |store| store.update_meta(&rowids, f)).await
+}
+
+pub async fn delete(rowids: Vec<i64>) -> Result<(), Box<dyn std::error::Error>> {
+    with_store(move |store| store.delete(&rowids)).await
+}
+
+pub async fn retire(rowids: Vec<i64>, fired_at: i64) -> Result<(), Box<dyn std::error::Error>> {
+    with_store(move |store| store.retire(&rowids, fired_at)).await
+}
+
+pub async fn fired_file_triggers() -> Result<HashMap<String, Trigger>, Box<dyn std::error::Error>> {
+    with_store(|store| store.fired_file_triggers()).await
+}
+
+pub async fn forget_fired(file_keys: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+    with_store(move |store| store.forget_fired(&file_keys)).await
+}
+
+pub async fn first_point(coin: String, currency: String) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+    with_store(move |store| store.first_point(&coin, &currency)).await
+}
+
+pub async fn set_first_point(coin: String, currency: String, first_point: i64) -> Result<(), Box<dyn std::error::Error>> {
+    with_store(move |store| store.set_first_point(&coin, &currency, first_point)).await
+}
@@ -0,0 +1,207 @@
+use std::{collections::HashMap, path::{Path, PathBuf}, time::SystemTime};
+
+use chrono::{Local, NaiveDateTime};
+use serde::Deserialize;
+
+use coingecko_requests::data::{Coin, VsCurrency};
+
+use crate::{trigger_meta::TriggerMeta, trigger_store::{self, Trigger}};
+
+/// A `triggers.toml` looks like this:
+///
+/// ```toml
+/// [[trigger]]
+/// id = "btc-ath"
+/// coin = "bitcoin"
+/// currency = "usd"
+/// target_price = 100000
+/// # optional, the current price is used when missing
+/// initial_price = 60000
+/// # optional, unix seconds
+/// expires_at = 1767225600
+/// # optional
+/// group = "swing levels"
+/// note = "breakout above the previous high"
+/// labels = ["swing", "btc"]
+/// ```
+///
+/// `id` ties an entry to the trigger created for it, so that entries can be changed and removed later.
+/// Once that trigger fires, the entry is left alone until its coin, currency, prices or expiry change.
+#[derive(Debug, Deserialize)]
+struct TriggersFile {
+    #[serde(default, rename = "trigger")]
+    triggers: Vec<FileTrigger>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct FileTrigger {
+    id: String,
+    coin: String,
+    currency: String,
+    target_price: f64,
+    #[serde(default)]
+    initial_price: Option<f64>,
+    #[serde(default)]
+    expires_at: Option<i64>,
+    #[serde(default)]
+    group: Option<String>,
+    #[serde(default)]
+    note: Option<String>,
+    #[serde(default)]
+    labels: Vec<String>,
+}
+
+/// The outcome of the latest sync, shown on the triggers tab.
+#[derive(Debug, Default)]
+pub struct TriggersFileStatus {
+    pub path: Option<PathBuf>,
+    pub errors: Vec<String>,
+    pub synced_at: Option<NaiveDateTime>,
+}
+
+/// Remembers what the file looked like when it was last synced, so it is only reconciled after it changes.
+#[derive(Default)]
+pub struct Watcher {
+    last_seen: Option<(PathBuf, SystemTime)>,
+}
+
+impl Watcher {
+    /// Reconciles the trigger store with the file when the configured path or the file's
+    /// modification time changed since the previous call. Returns the new status in that case.
+    pub async fn poll(&mut self, path: Option<PathBuf>, coins: &[coingecko_requests::data::Coin], currencies: &[coingecko_requests::data::VsCurrency]) -> Option<TriggersFileStatus> {
+        let path = match path {
+            Some(path) => path,
+            None => {
+                return self.last_seen.take().map(|_| Default::default());
+            }
+        };
+        let modified = tokio::fs::metadata(&path).await.and_then(|metadata| metadata.modified()).ok();
+        let seen = modified.map(|modified| (path.clone(), modified));
+        if seen.is_some() && seen == self.last_seen {
+            return None;
+        }
+        let errors = match modified {
+            Some(_) => {
+                match reconcile(&path, coins, currencies).await.map_err(|err| err.to_string()) {
+                    Ok(errors) => {
+                        self.last_seen = seen;
+                        errors
+                    }
+                    // A reconcile can stop halfway, so the file is not marked as seen and the next poll retries it.
+                    Err(err) => vec![err],
+                }
+            }
+            None => {
+                self.last_seen = None;
+                vec![format!("{} cannot be read", path.display())]
+            }
+        };
+        Some(TriggersFileStatus {
+            path: Some(path),
+            errors,
+            synced_at: Some(Local::now().naive_local()),
+        })
+    }
+}
+
+/// Adds, updates and retires the triggers owned by the file. Entries that fail validation are
+/// reported and left alone, so a typo does not delete the trigger that entry used to describe.
+async fn reconcile(path: &Path, coins: &[coingecko_requests::data::Coin], currencies: &[coingecko_requests::data::VsCurrency]) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let content = tokio::fs::read_to_string(path).await?;
+    let file: TriggersFile = toml::from_str(&content)?;
+
+    let mut errors = Vec::new();
+    let mut entries = HashMap::new();
+    let mut invalid = Vec::new();
+    for entry in file.triggers {
+        let coin = coins.iter().find(|coin| coin.raw.id == entry.coin).cloned();
+        let currency = currencies.iter().find(|currency| currency.raw.name == entry.currency).cloned();
+        let error = if entries.contains_key(&entry.id) || invalid.contains(&entry.id) {
+            Some(format!("id '{}' is used more than once", entry.id))
+        } else if coin.is_none() {
+            Some(format!("'{}': unknown coin '{}'", entry.id, entry.coin))
+        } else if currency.is_none() {
+            Some(format!("'{}': unknown currency '{}'", entry.id, entry.currency))
+        } else if entry.target_price <= 0.0 || entry.initial_price.map(|price| price <= 0.0).unwrap_or(false) {
+            Some(format!("'{}': prices must be positive", entry.id))
+        } else {
+            None
+        };
+        match error {
+            Some(error) => {
+                errors.push(error);
+                entries.remove(&entry.id);
+                invalid.push(entry.id);
+            }
+            None => {
+                entries.insert(entry.id.clone(), (coin.unwrap(), currency.unwrap(), entry));
+            }
+        }
+    }
+
+    let mut owned = HashMap::new();
+    for trigger in trigger_store::load().await? {
+        if let Some(file_key) = trigger.meta.file_key.clone() {
+            owned.insert(file_key, trigger);
+        }
+    }
+
+    for (id, trigger) in owned.iter() {
+        if !entries.contains_key(id) && !invalid.contains(id) {
+            trigger_store::delete(vec![trigger.rowid]).await?;
+        }
+    }
+
+    // A fired entry stays retired until it is changed or taken out of the file.
+    let fired = trigger_store::fired_file_triggers().await?;
+    let forgotten: Vec<String> = fired.keys()
+        .filter(|id| !entries.contains_key(*id) && !invalid.contains(*id))
+        .cloned()
+        .collect();
+    if !forgotten.is_empty() {
+        trigger_store::forget_fired(forgotten).await?;
+    }
+
+    for (id, (coin, currency, entry)) in entries {
+        if let Some(trigger) = owned.get(&id) {
+            let meta = &trigger.meta;
+            if describes(&entry, &coin, &currency, trigger) {
+                // Changing the group, note or labels does not need a new trigger.
+                if meta.group != entry.group || meta.note != entry.note || meta.labels != entry.labels {
+                    trigger_store::update_meta(vec![trigger.rowid], move |meta| {
+                        meta.group = entry.group.clone();
+                        meta.note = entry.note.clone();
+                        meta.labels = entry.labels.clone();
+                    }).await?;
+                }
+                continue;
+            }
+            trigger_store::delete(vec![trigger.rowid]).await?;
+        } else if let Some(trigger) = fired.get(&id) {
+            if describes(&entry, &coin, &currency, trigger) {
+                continue;
+            }
+            trigger_store::forget_fired(vec![id.clone()]).await?;
+        }
+        let meta = TriggerMeta {
+            expires_at: entry.expires_at,
+            file_key: Some(id),
+            group: entry.group,
+            note: entry.note,
+            labels: entry.labels,
+            ..Default::default()
+        };
+        trigger_store::add(coin, currency, entry.initial_price, entry.target_price, meta).await?;
+    }
+
+    Ok(errors)
+}
+
+/// Whether `trigger` is the one `entry` asks for. The group, note and labels are left out.
+fn describes(entry: &FileTrigger, coin: &Coin, currency: &VsCurrency, trigger: &Trigger) -> bool {
+    trigger.coin_id == coin.rowid
+        && trigger.currency_id == currency.rowid
+        && trigger.target_price == entry.target_price
+        && entry.initial_price.map(|price| price == trigger.initial_price).unwrap_or(true)
+        && trigger.meta.expires_at == entry.expires_at
+}
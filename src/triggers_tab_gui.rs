@@ -1,23 +1,133 @@
-use std::{rc::Rc, sync::{Arc, RwLock}};
+use std::{fmt::Display, path::PathBuf, rc::Rc, sync::{Arc, RwLock}};
+use chrono::Local;
 use coingecko_requests::data::{Coin, VsCurrency};
-use iced::{Button, Clipboard, Column, Command, HorizontalAlignment, Length, PickList, Row, Scrollable, Text, TextInput, button, pick_list, scrollable, text_input};
+use directories::ProjectDirs;
+use iced::{Button, Clipboard, Color, Column, Command, HorizontalAlignment, Length, PickList, ProgressBar, Row, Scrollable, Text, TextInput, button, pick_list, scrollable, text_input};
+
+use crate::{trigger_io::{self, ImportPreview, RecordStatus, TransferFormat, TriggerRecord}, trigger_list::{Direction, Filter, ListItem, ListOptions, SortKey}, trigger_meta::{self, TriggerMeta, TriggerState}, trigger_store::{self, Trigger}};
+
+/// Triggers whose target is at most this far from the latest price are highlighted.
+const NEAR_TARGET_PERCENT: f64 = 1.0;
+
+const SNOOZE_SECONDS: i64 = 60 * 60;
+
+/// New targets further than this from the current price are most likely typos.
+const IMPLAUSIBLE_MOVE_PERCENT: f64 = 90.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expiry {
+    Never,
+    Day,
+    Week,
+    Month,
+}
+
+impl Display for Expiry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expiry::Never => { write!(f, "Never") }
+            Expiry::Day => { write!(f, "In a day") }
+            Expiry::Week => { write!(f, "In a week") }
+            Expiry::Month => { write!(f, "In a month") }
+        }
+    }
+}
+
+impl Expiry {
+    pub const ALL: [Expiry; 4] = [Expiry::Never, Expiry::Day, Expiry::Week, Expiry::Month];
+
+    pub fn seconds(&self) -> Option<i64> {
+        match self {
+            Expiry::Never => None,
+            Expiry::Day => Some(60*60*24),
+            Expiry::Week => Some(60*60*24*7),
+            Expiry::Month => Some(60*60*24*30),
+        }
+    }
+}
+
+impl Default for Expiry {
+    fn default() -> Self {
+        Expiry::Never
+    }
+}
+
+/// What the buttons on a group header do to the triggers of that group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupAction {
+    Enable,
+    Disable,
+    Snooze,
+    Delete,
+    Export,
+}
+
+impl Display for GroupAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GroupAction::Enable => { write!(f, "enable") }
+            GroupAction::Disable => { write!(f, "disable") }
+            GroupAction::Snooze => { write!(f, "snooze") }
+            GroupAction::Delete => { write!(f, "delete all") }
+            GroupAction::Export => { write!(f, "export") }
+        }
+    }
+}
+
+impl GroupAction {
+    pub const ALL: [GroupAction; 5] = [GroupAction::Enable, GroupAction::Disable, GroupAction::Snooze, GroupAction::Delete, GroupAction::Export];
+}
 
 pub struct Flags {
     pub coins: Rc<Vec<coingecko_requests::data::Coin>>,
     pub currencies: Rc<Vec<coingecko_requests::data::VsCurrency>>,
     pub settings: Arc<RwLock<crate::settings::Settings>>,
+    pub prices: Arc<RwLock<crate::trigger_engine::PriceSnapshot>>,
+    pub triggers_file_status: Arc<RwLock<crate::triggers_file::TriggersFileStatus>>,
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
-    TriggersUpdated(Vec<coingecko_requests::data::Trigger>),
+    TriggersUpdated(Result<Vec<Trigger>, String>),
     SaveTriggerClicked,
     CoinPicked(coingecko_requests::data::Coin),
     CurrencyPicked(coingecko_requests::data::VsCurrency),
+    /// The current price of the `(coin rowid, currency rowid)` pair picked on the form.
+    LivePriceFetched(i64, i64, Result<f64, String>),
+    ExpiryPicked(Expiry),
     PriceInputChanged(String),
-    TriggersAdded,
+    GroupInputChanged(String),
+    GroupPicked(String),
+    NoteInputChanged(String),
+    LabelsInputChanged(String),
+    TriggersAdded(Result<i64, String>),
     DeleteTriggerClicked(i64),
-    TriggerDeleted
+    TriggerDeleted(Result<(), String>),
+    SnoozeClicked(i64),
+    WakeClicked(i64),
+    EnableClicked(i64),
+    TriggerMetaUpdated(Result<(), String>),
+    GroupToggled(Option<String>),
+    /// Applies to the listed triggers of the group, i.e. the ones the current filters let through.
+    GroupActionClicked(Option<String>, GroupAction, Vec<i64>),
+    ConfirmGroupDeleteClicked,
+    CancelGroupDeleteClicked,
+    SortPicked(SortKey),
+    SortOrderToggled,
+    CoinFilterPicked(Filter<String>),
+    CurrencyFilterPicked(Filter<String>),
+    DirectionFilterPicked(Filter<Direction>),
+    StateFilterPicked(Filter<TriggerState>),
+    SearchChanged(String),
+    SearchSubmitted,
+    TransferPathChanged(String),
+    ExportClicked(TransferFormat),
+    Exported(Result<(PathBuf, usize), String>),
+    ImportClicked,
+    ImportRead(Result<Vec<TriggerRecord>, String>),
+    ConfirmImportClicked,
+    CancelImportClicked,
+    Imported(Result<usize, String>),
 }
 
 #[derive(Debug, Clone)]
@@ -25,68 +135,353 @@ pub struct Gui {
     coins: Rc<Vec<coingecko_requests::data::Coin>>,
     currencies: Rc<Vec<coingecko_requests::data::VsCurrency>>,
     settings: Arc<RwLock<crate::settings::Settings>>,
-    triggers: Vec<coingecko_requests::data::Trigger>,
+    prices: Arc<RwLock<crate::trigger_engine::PriceSnapshot>>,
+    triggers_file_status: Arc<RwLock<crate::triggers_file::TriggersFileStatus>>,
+    triggers: Vec<Trigger>,
     picked_coin: coingecko_requests::data::Coin,
     coin_picklist_state: pick_list::State<coingecko_requests::data::Coin>,
     picked_currency: coingecko_requests::data::VsCurrency,
     currency_picklist_state: pick_list::State<coingecko_requests::data::VsCurrency>,
+    picked_expiry: Expiry,
+    expiry_picklist_state: pick_list::State<Expiry>,
     price_input_state: text_input::State,
     price_value: String,
+    live_price: Option<Result<f64, String>>,
+    save_error: Option<String>,
+    /// The latest failure to load or change the triggers in the store.
+    store_error: Option<String>,
+    settings_error: Option<String>,
+    group_input_state: text_input::State,
+    group_value: String,
+    group_picklist_state: pick_list::State<String>,
+    note_input_state: text_input::State,
+    note_value: String,
+    labels_input_state: text_input::State,
+    labels_value: String,
     save_trigger_state: button::State,
+    sort_picklist_state: pick_list::State<SortKey>,
+    sort_order_button_state: button::State,
+    coin_filter_picklist_state: pick_list::State<Filter<String>>,
+    currency_filter_picklist_state: pick_list::State<Filter<String>>,
+    direction_filter_picklist_state: pick_list::State<Filter<Direction>>,
+    state_filter_picklist_state: pick_list::State<Filter<TriggerState>>,
+    search_input_state: text_input::State,
+    transfer_path: String,
+    transfer_path_input_state: text_input::State,
+    export_csv_button_state: button::State,
+    export_json_button_state: button::State,
+    import_button_state: button::State,
+    confirm_import_button_state: button::State,
+    cancel_import_button_state: button::State,
+    import_preview: Option<ImportPreview>,
+    transfer_status: Option<String>,
     scrollable_state: scrollable::State,
     delete_button_states: Vec<button::State>,
+    snooze_button_states: Vec<button::State>,
+    collapsed_groups: Vec<Option<String>>,
+    group_button_states: Vec<(button::State, [button::State; 5])>,
+    /// A group whose triggers are deleted once the user confirms it, with the rowids to delete.
+    pending_group_delete: Option<(Option<String>, Vec<i64>)>,
+    confirm_group_delete_button_state: button::State,
+    cancel_group_delete_button_state: button::State,
+    focused_trigger: Option<i64>,
 }
 
 impl Gui {
     pub fn new(flags: Flags) -> (Self, Command<Message>) {
         let picked_coin = flags.coins.iter().find(|coin| coin.raw.id == "bitcoin").cloned().unwrap();
         let picked_currency = flags.currencies.iter().find(|currency| currency.raw.name == "usd").cloned().unwrap();
+        let transfer_path = ProjectDirs::from("org", "jna", "jna")
+            .map(|project_dirs| project_dirs.data_dir().join("jna_triggers.csv").to_string_lossy().to_string())
+            .unwrap_or_default();
 
         (Self{
             coins: flags.coins,
             currencies: flags.currencies,
             settings: flags.settings,
+            prices: flags.prices,
+            triggers_file_status: flags.triggers_file_status,
             triggers: Vec::new(),
             coin_picklist_state: Default::default(),
             picked_coin: picked_coin.clone(),
             picked_currency: picked_currency.clone(),
             currency_picklist_state: Default::default(),
+            picked_expiry: Default::default(),
+            expiry_picklist_state: Default::default(),
             price_input_state: Default::default(),
             price_value: Default::default(),
+            live_price: None,
+            save_error: None,
+            store_error: None,
+            settings_error: None,
+            group_input_state: Default::default(),
+            group_value: Default::default(),
+            group_picklist_state: Default::default(),
+            note_input_state: Default::default(),
+            note_value: Default::default(),
+            labels_input_state: Default::default(),
+            labels_value: Default::default(),
             save_trigger_state: Default::default(),
+            sort_picklist_state: Default::default(),
+            sort_order_button_state: Default::default(),
+            coin_filter_picklist_state: Default::default(),
+            currency_filter_picklist_state: Default::default(),
+            direction_filter_picklist_state: Default::default(),
+            state_filter_picklist_state: Default::default(),
+            search_input_state: Default::default(),
+            transfer_path,
+            transfer_path_input_state: Default::default(),
+            export_csv_button_state: Default::default(),
+            export_json_button_state: Default::default(),
+            import_button_state: Default::default(),
+            confirm_import_button_state: Default::default(),
+            cancel_import_button_state: Default::default(),
+            import_preview: None,
+            transfer_status: None,
             scrollable_state: Default::default(),
             delete_button_states: Vec::new(),
-        }, Command::perform(update_triggers(), |result| Message::TriggersUpdated(result.unwrap())))
+            snooze_button_states: Vec::new(),
+            collapsed_groups: Vec::new(),
+            group_button_states: Vec::new(),
+            pending_group_delete: None,
+            confirm_group_delete_button_state: Default::default(),
+            cancel_group_delete_button_state: Default::default(),
+            focused_trigger: None,
+        }, Command::batch(vec![
+            reload_triggers(),
+            fetch_live_price(&picked_coin, &picked_currency),
+        ]))
+    }
+
+    /// Highlights a trigger in the list, e.g. when a notification about it is opened.
+    pub fn focus_trigger(&mut self, rowid: i64) {
+        let group = self.triggers.iter().find(|trigger| trigger.rowid == rowid).and_then(|trigger| trigger.meta.group.clone());
+        self.collapsed_groups.retain(|collapsed| *collapsed != group);
+        self.focused_trigger = Some(rowid);
+    }
+
+    /// The given triggers without the ones owned by the triggers file, which can only be changed by editing the file.
+    fn editable(&self, rowids: &[i64]) -> Vec<i64> {
+        self.triggers.iter()
+            .filter(|trigger| rowids.contains(&trigger.rowid) && trigger.meta.file_key.is_none())
+            .map(|trigger| trigger.rowid)
+            .collect()
+    }
+
+    fn update_list_options<F: FnOnce(&mut ListOptions)>(&mut self, f: F) {
+        f(&mut self.settings.write().unwrap().trigger_list);
+        self.save_list_options();
+    }
+
+    fn save_list_options(&mut self) {
+        self.settings_error = self.settings.read().unwrap().save().err().map(|err| format!("The list options could not be saved: {}", err));
     }
 
     pub fn update(&mut self, message: Message, _clipboard: &mut Clipboard) -> Command<Message> {
         match message {
-            Message::TriggersUpdated(vec) => {
+            Message::TriggersUpdated(Ok(vec)) => {
                 println!("Triggers updated. len = {}", vec.len());
                 self.triggers = vec;
             }
+            Message::TriggersUpdated(Err(err)) => {
+                self.store_error = Some(format!("The triggers could not be loaded: {}", err));
+            }
             Message::PriceInputChanged(value) => {
                 self.price_value = value;
+                self.save_error = None;
             }
             Message::CoinPicked(picked) => {
                 self.picked_coin = picked;
+                self.live_price = None;
+                return fetch_live_price(&self.picked_coin, &self.picked_currency);
             }
             Message::CurrencyPicked(picked) => {
                 self.picked_currency = picked;
+                self.live_price = None;
+                return fetch_live_price(&self.picked_coin, &self.picked_currency);
+            }
+            Message::LivePriceFetched(coin_id, currency_id, result) => {
+                // The pair may have changed while the price was being fetched.
+                if coin_id == self.picked_coin.rowid && currency_id == self.picked_currency.rowid {
+                    self.live_price = Some(result);
+                }
+            }
+            Message::ExpiryPicked(picked) => {
+                self.picked_expiry = picked;
+            }
+            Message::GroupInputChanged(value) | Message::GroupPicked(value) => {
+                self.group_value = value;
+            }
+            Message::NoteInputChanged(value) => {
+                self.note_value = value;
+            }
+            Message::LabelsInputChanged(value) => {
+                self.labels_value = value;
             }
             Message::SaveTriggerClicked => {
-                if let Ok(value) = self.price_value.parse::<f64>() {
-                    return Command::perform(add_trigger(self.picked_coin.clone(), self.picked_currency.clone(), value), |x| { x.unwrap(); Message::TriggersAdded });
+                let live_price = self.live_price.clone().and_then(Result::ok);
+                match check_target(&self.price_value, live_price) {
+                    Err(err) => {
+                        self.save_error = Some(err);
+                    }
+                    Ok((value, _)) => {
+                        let group = self.group_value.trim();
+                        let note = self.note_value.trim();
+                        let meta = TriggerMeta {
+                            expires_at: self.picked_expiry.seconds().map(|seconds| Local::now().timestamp() + seconds),
+                            group: if group.is_empty() { None } else { Some(group.to_string()) },
+                            note: if note.is_empty() { None } else { Some(note.to_string()) },
+                            labels: trigger_meta::parse_labels(&self.labels_value),
+                            ..Default::default()
+                        };
+                        self.save_error = None;
+                        // The shown price may be hours old, so the store fetches the one that decides the direction.
+                        return Command::perform(trigger_store::add(self.picked_coin.clone(), self.picked_currency.clone(), None, value, meta), |result| Message::TriggersAdded(result.map_err(|err| err.to_string())));
+                    }
                 }
             }
-            Message::TriggersAdded => {
-                return Command::perform(update_triggers(), |result| Message::TriggersUpdated(result.unwrap()));
+            Message::TriggersAdded(Ok(_)) => {
+                self.price_value.clear();
+                self.note_value.clear();
+                return Command::batch(vec![reload_triggers(), fetch_live_price(&self.picked_coin, &self.picked_currency)]);
+            }
+            Message::TriggersAdded(Err(err)) => {
+                self.save_error = Some(format!("The trigger could not be saved: {}", err));
+            }
+            Message::TriggerDeleted(result) => {
+                self.store_error = result.err().map(|err| format!("The trigger(s) could not be deleted: {}", err));
+                return reload_triggers();
+            }
+            Message::TriggerMetaUpdated(result) => {
+                self.store_error = result.err().map(|err| format!("The trigger(s) could not be changed: {}", err));
+                return reload_triggers();
             }
             Message::DeleteTriggerClicked(id) => {
-                return Command::perform(delete_trigger(id), |result| { result.unwrap(); Message::TriggerDeleted });
+                return delete_triggers(vec![id]);
+            }
+            Message::SnoozeClicked(id) => {
+                let until = Local::now().timestamp() + SNOOZE_SECONDS;
+                return update_meta(vec![id], move |meta| meta.snoozed_until = Some(until));
+            }
+            Message::WakeClicked(id) => {
+                return update_meta(vec![id], |meta| meta.snoozed_until = None);
+            }
+            Message::EnableClicked(id) => {
+                return update_meta(vec![id], |meta| meta.disabled = false);
+            }
+            Message::GroupToggled(group) => {
+                if self.collapsed_groups.contains(&group) {
+                    self.collapsed_groups.retain(|collapsed| *collapsed != group);
+                } else {
+                    self.collapsed_groups.push(group);
+                }
+            }
+            Message::GroupActionClicked(group, action, rowids) => {
+                match action {
+                    GroupAction::Enable => {
+                        return update_meta(self.editable(&rowids), |meta| meta.disabled = false);
+                    }
+                    GroupAction::Disable => {
+                        return update_meta(self.editable(&rowids), |meta| meta.disabled = true);
+                    }
+                    GroupAction::Snooze => {
+                        let until = Local::now().timestamp() + SNOOZE_SECONDS;
+                        return update_meta(self.editable(&rowids), move |meta| meta.snoozed_until = Some(until));
+                    }
+                    GroupAction::Delete => {
+                        // Deleting a whole group cannot be undone, so it waits for a confirmation.
+                        self.pending_group_delete = Some((group, self.editable(&rowids)));
+                    }
+                    GroupAction::Export => {
+                        let triggers = self.triggers.iter().filter(|trigger| rowids.contains(&trigger.rowid)).cloned().collect::<Vec<_>>();
+                        let records = trigger_io::records(&triggers, &self.coins, &self.currencies);
+                        let name = group.unwrap_or_else(|| "ungrouped".to_string())
+                            .chars()
+                            .map(|c| if c.is_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+                            .collect::<String>();
+                        let path = PathBuf::from(&self.transfer_path).with_file_name(format!("jna_group_{}.csv", name));
+                        return Command::perform(export_triggers(path, records, TransferFormat::Csv), |result| Message::Exported(result.map_err(|err| err.to_string())));
+                    }
+                }
+            }
+            Message::ConfirmGroupDeleteClicked => {
+                if let Some((_, rowids)) = self.pending_group_delete.take() {
+                    return delete_triggers(rowids);
+                }
+            }
+            Message::CancelGroupDeleteClicked => {
+                self.pending_group_delete = None;
+            }
+            Message::SortPicked(sort) => {
+                self.update_list_options(|options| options.sort = sort);
+            }
+            Message::SortOrderToggled => {
+                self.update_list_options(|options| options.descending = !options.descending);
+            }
+            Message::CoinFilterPicked(filter) => {
+                self.update_list_options(|options| options.coin = filter.into_option());
+            }
+            Message::CurrencyFilterPicked(filter) => {
+                self.update_list_options(|options| options.currency = filter.into_option());
+            }
+            Message::DirectionFilterPicked(filter) => {
+                self.update_list_options(|options| options.direction = filter.into_option());
+            }
+            Message::StateFilterPicked(filter) => {
+                self.update_list_options(|options| options.state = filter.into_option());
+            }
+            Message::SearchChanged(search) => {
+                // The settings file is only written once the search is submitted, or with the next change of the other options.
+                self.settings.write().unwrap().trigger_list.search = search;
+            }
+            Message::SearchSubmitted => {
+                self.save_list_options();
             }
-            Message::TriggerDeleted => {
-                return Command::perform(update_triggers(), |result| Message::TriggersUpdated(result.unwrap()));
+            Message::TransferPathChanged(path) => {
+                self.transfer_path = path;
+            }
+            Message::ExportClicked(format) => {
+                let records = trigger_io::records(&self.triggers, &self.coins, &self.currencies);
+                let path = PathBuf::from(&self.transfer_path).with_extension(format.extension());
+                return Command::perform(export_triggers(path, records, format), |result| Message::Exported(result.map_err(|err| err.to_string())));
+            }
+            Message::Exported(Ok((path, count))) => {
+                self.transfer_path = path.to_string_lossy().to_string();
+                self.transfer_status = Some(format!("Exported {} trigger(s) to {}", count, self.transfer_path));
+            }
+            Message::Exported(Err(err)) => {
+                self.transfer_status = Some(format!("Export failed: {}", err));
+            }
+            Message::ImportClicked => {
+                return Command::perform(read_triggers(PathBuf::from(&self.transfer_path)), |result| Message::ImportRead(result.map_err(|err| err.to_string())));
+            }
+            Message::ImportRead(Ok(records)) => {
+                let preview = ImportPreview::new(records, &self.coins, &self.currencies, &self.triggers, &self.prices.read().unwrap());
+                self.transfer_status = Some(format!("{} of {} trigger(s) can be imported", preview.new_count(), preview.rows.len()));
+                self.import_preview = Some(preview);
+            }
+            Message::ImportRead(Err(err)) => {
+                self.transfer_status = Some(format!("Import failed: {}", err));
+            }
+            Message::ConfirmImportClicked => {
+                if let Some(preview) = self.import_preview.take() {
+                    let new = preview.rows.into_iter().filter_map(|(record, status)| match status {
+                        RecordStatus::New(coin, currency) => Some((coin, currency, record)),
+                        _ => None
+                    }).collect();
+                    return Command::perform(import_triggers(new), |result| Message::Imported(result.map_err(|err| err.to_string())));
+                }
+            }
+            Message::CancelImportClicked => {
+                self.import_preview = None;
+                self.transfer_status = None;
+            }
+            Message::Imported(result) => {
+                self.transfer_status = Some(match result {
+                    Ok(count) => format!("Imported {} trigger(s)", count),
+                    Err(err) => format!("Import failed: {}", err),
+                });
+                return reload_triggers();
             }
         }
         Command::none()
@@ -95,20 +490,18 @@ impl Gui {
     pub fn view(&mut self) -> iced::Element<'_, Message> {
         let lock = self.settings.read().unwrap();
         let theme = lock.theme;
+        let mut list_options = lock.trigger_list.clone();
 
         let show_all_coins = lock.show_all_coins;
         let show_all_currencies = lock.show_all_currencies;
         let coins = if show_all_coins { self.coins.as_ref().clone() } else { self.coins.iter().filter(|coin| coin.favourite).cloned().collect() };
         let currencies = if show_all_currencies { self.currencies.as_ref().clone() } else { self.currencies.iter().filter(|coin| coin.favourite).cloned().collect() };
 
-        self.delete_button_states = vec![Default::default(); self.triggers.len()];
-        let mut delete_button_states = self.delete_button_states.iter_mut().collect::<Vec<_>>();
-
         let mut main_column = Column::new()
             .spacing(5)
             .width(Length::Fill)
             .height(Length::Fill);
-        
+
         let mut trigger_settings_row = Row::new()
             .spacing(5)
             .width(Length::Shrink)
@@ -116,7 +509,7 @@ impl Gui {
 
         let mut coin_column = Column::new()
             .spacing(5)
-            .width(Length::FillPortion(1)); 
+            .width(Length::FillPortion(1));
         coin_column = coin_column.push(Text::new("Coin"));
         let coin_picklist = PickList::new(&mut self.coin_picklist_state, coins, Some(self.picked_coin.clone()), Message::CoinPicked).width(Length::Fill).style(theme);
         coin_column = coin_column.push(coin_picklist);
@@ -135,25 +528,290 @@ impl Gui {
         let text_input_price = TextInput::new(&mut self.price_input_state,"200",&mut self.price_value ,Message::PriceInputChanged).width(Length::Fill).padding(5).style(theme);
         price_input_column = price_input_column.push(text_input_price);
 
+        let mut expiry_column = Column::new()
+            .spacing(5)
+            .width(Length::FillPortion(1));
+        expiry_column = expiry_column.push(Text::new("Expires"));
+        let expiry_picklist = PickList::new(&mut self.expiry_picklist_state, &Expiry::ALL[..], Some(self.picked_expiry), Message::ExpiryPicked).width(Length::Fill).style(theme);
+        expiry_column = expiry_column.push(expiry_picklist);
+
         trigger_settings_row = trigger_settings_row.push(coin_column);
         trigger_settings_row = trigger_settings_row.push(vs_currency_column);
         trigger_settings_row = trigger_settings_row.push(price_input_column);
+        trigger_settings_row = trigger_settings_row.push(expiry_column);
         trigger_settings_row = trigger_settings_row.push(Button::new(&mut self.save_trigger_state, Text::new("Save").horizontal_alignment(HorizontalAlignment::Center)).on_press(Message::SaveTriggerClicked).width(Length::Fill).padding(17).style(theme));
 
         main_column = main_column.push(trigger_settings_row);
 
-        let mut scrollable = Scrollable::new(&mut self.scrollable_state)
+        let live_price = self.live_price.clone().and_then(Result::ok);
+        let mut validation_row = Row::new()
+            .spacing(10)
             .width(Length::Fill)
-            .height(Length::Fill);
+            .height(Length::Shrink);
+        match self.live_price {
+            Some(Ok(price)) => {
+                validation_row = validation_row.push(Text::new(format!("Current price: {}", price)).size(16));
+            }
+            Some(Err(ref err)) => {
+                validation_row = validation_row.push(Text::new(format!("Current price unavailable: {}", err)).size(16).color(Color::from_rgb8(0xD0, 0x3B, 0x3B)));
+            }
+            None => {
+                validation_row = validation_row.push(Text::new("Current price: loading...").size(16));
+            }
+        }
+        if let Some(ref err) = self.save_error {
+            validation_row = validation_row.push(Text::new(err.clone()).size(16).color(Color::from_rgb8(0xD0, 0x3B, 0x3B)));
+        } else if !self.price_value.trim().is_empty() {
+            match check_target(&self.price_value, live_price) {
+                Ok((target, warning)) => {
+                    if let Some(price) = live_price {
+                        validation_row = validation_row.push(Text::new(format!("{:+.2}% from the current price", (target - price) / price * 100.0)).size(16));
+                    }
+                    if let Some(warning) = warning {
+                        validation_row = validation_row.push(Text::new(warning).size(16).color(Color::from_rgb8(0xE0, 0x8E, 0x0B)));
+                    }
+                }
+                Err(err) => {
+                    validation_row = validation_row.push(Text::new(err).size(16).color(Color::from_rgb8(0xD0, 0x3B, 0x3B)));
+                }
+            }
+        }
+        main_column = main_column.push(validation_row);
 
+        let mut group_row = Row::new()
+            .spacing(5)
+            .width(Length::Fill)
+            .height(Length::Shrink);
+        group_row = group_row.push(Text::new("Group"));
+        group_row = group_row.push(TextInput::new(&mut self.group_input_state, "No group", &self.group_value, Message::GroupInputChanged).width(Length::FillPortion(2)).padding(5).style(theme));
+        let mut groups = self.triggers.iter().filter_map(|trigger| trigger.meta.group.clone()).collect::<Vec<_>>();
+        groups.sort();
+        groups.dedup();
+        if !groups.is_empty() {
+            group_row = group_row.push(PickList::new(&mut self.group_picklist_state, groups, None, Message::GroupPicked).width(Length::FillPortion(1)).style(theme));
+        }
+        main_column = main_column.push(group_row);
+
+        let mut annotations_row = Row::new()
+            .spacing(5)
+            .width(Length::Fill)
+            .height(Length::Shrink);
+        annotations_row = annotations_row.push(Text::new("Labels"));
+        annotations_row = annotations_row.push(TextInput::new(&mut self.labels_input_state, "swing, btc", &self.labels_value, Message::LabelsInputChanged).width(Length::FillPortion(1)).padding(5).style(theme));
+        annotations_row = annotations_row.push(Text::new("Note"));
+        annotations_row = annotations_row.push(TextInput::new(&mut self.note_input_state, "Why does this alert exist?", &self.note_value, Message::NoteInputChanged).width(Length::FillPortion(2)).padding(5).style(theme));
+        main_column = main_column.push(annotations_row);
+
+        let mut transfer_row = Row::new()
+            .spacing(5)
+            .width(Length::Fill)
+            .height(Length::Shrink);
+        transfer_row = transfer_row.push(TextInput::new(&mut self.transfer_path_input_state, "File path", &self.transfer_path, Message::TransferPathChanged).width(Length::Fill).padding(5).style(theme));
+        transfer_row = transfer_row.push(Button::new(&mut self.export_csv_button_state, Text::new("Export CSV")).on_press(Message::ExportClicked(TransferFormat::Csv)).style(theme));
+        transfer_row = transfer_row.push(Button::new(&mut self.export_json_button_state, Text::new("Export JSON")).on_press(Message::ExportClicked(TransferFormat::Json)).style(theme));
+        transfer_row = transfer_row.push(Button::new(&mut self.import_button_state, Text::new("Import")).on_press(Message::ImportClicked).style(theme));
+        main_column = main_column.push(transfer_row);
+
+        if let Some(ref status) = self.transfer_status {
+            main_column = main_column.push(Text::new(status.clone()).size(16));
+        }
+
+        if let Some(ref preview) = self.import_preview {
+            let mut preview_column = Column::new()
+                .spacing(2)
+                .width(Length::Fill);
+            for (record, status) in preview.rows.iter() {
+                let mut record_text = Text::new(format!("{} / {}: target {}, {}", record.coin, record.currency, record.target_price, status)).size(16);
+                if !matches!(status, RecordStatus::New(_, _)) {
+                    record_text = record_text.color(Color::from_rgb8(0xD0, 0x3B, 0x3B));
+                }
+                preview_column = preview_column.push(record_text);
+            }
+            let mut preview_buttons_row = Row::new()
+                .spacing(5);
+            preview_buttons_row = preview_buttons_row.push(Button::new(&mut self.confirm_import_button_state, Text::new(format!("Import {} trigger(s)", preview.new_count()))).on_press(Message::ConfirmImportClicked).style(theme));
+            preview_buttons_row = preview_buttons_row.push(Button::new(&mut self.cancel_import_button_state, Text::new("Cancel")).on_press(Message::CancelImportClicked).style(theme));
+            preview_column = preview_column.push(preview_buttons_row);
+            main_column = main_column.push(preview_column);
+        }
+
+        let triggers_file_status = self.triggers_file_status.read().unwrap();
+        if let Some(ref path) = triggers_file_status.path {
+            let synced_at = triggers_file_status.synced_at.map(|synced_at| synced_at.format("%H:%M:%S").to_string()).unwrap_or_default();
+            main_column = main_column.push(Text::new(format!("Triggers file {} synced at {}", path.display(), synced_at)).size(16));
+            for error in triggers_file_status.errors.iter() {
+                main_column = main_column.push(Text::new(error.clone()).size(16).color(Color::from_rgb8(0xD0, 0x3B, 0x3B)));
+            }
+        }
+        drop(triggers_file_status);
+
+        let now = Local::now().timestamp();
+        let prices = self.prices.read().unwrap();
+        let mut items = Vec::new();
         for trigger in self.triggers.iter() {
             let coin = self.coins.iter().find(|coin| coin.rowid == trigger.coin_id).cloned().unwrap();
             let currency = self.currencies.iter().find(|currency| currency.rowid == trigger.currency_id).cloned().unwrap();
+            let meta = &trigger.meta;
+            let current_price = prices.get(trigger.coin_id, trigger.currency_id).map(|(price, _)| price);
+            let item = ListItem {
+                rowid: trigger.rowid,
+                coin: coin.raw.id.clone(),
+                currency: currency.raw.name.clone(),
+                group: meta.group.clone(),
+                note: meta.note.clone(),
+                labels: meta.labels.clone(),
+                direction: Direction::of(trigger.initial_price, trigger.target_price),
+                state: meta.state(now),
+                created_at: meta.created_at,
+                distance: current_price.map(|price| (trigger.target_price - price) / price * 100.0),
+            };
+            items.push((trigger, current_price, meta.file_key.clone(), item));
+        }
+        drop(prices);
+
+        let mut coin_filters = items.iter().map(|(_, _, _, item)| item.coin.clone()).collect::<Vec<_>>();
+        coin_filters.sort();
+        coin_filters.dedup();
+        let coin_filters = std::iter::once(Filter::All).chain(coin_filters.into_iter().map(Filter::Only)).collect::<Vec<_>>();
+        let mut currency_filters = items.iter().map(|(_, _, _, item)| item.currency.clone()).collect::<Vec<_>>();
+        currency_filters.sort();
+        currency_filters.dedup();
+        let currency_filters = std::iter::once(Filter::All).chain(currency_filters.into_iter().map(Filter::Only)).collect::<Vec<_>>();
+        let direction_filters = std::iter::once(Filter::All).chain(Direction::ALL.iter().cloned().map(Filter::Only)).collect::<Vec<_>>();
+        let state_filters = std::iter::once(Filter::All).chain(TriggerState::ALL.iter().cloned().map(Filter::Only)).collect::<Vec<_>>();
+
+        let mut list_settings_row = Row::new()
+            .spacing(5)
+            .width(Length::Fill)
+            .height(Length::Shrink);
+        list_settings_row = list_settings_row.push(Text::new("Sort:"));
+        list_settings_row = list_settings_row.push(PickList::new(&mut self.sort_picklist_state, &SortKey::ALL[..], Some(list_options.sort), Message::SortPicked).width(Length::FillPortion(2)).style(theme));
+        list_settings_row = list_settings_row.push(Button::new(&mut self.sort_order_button_state, Text::new(if list_options.descending { "desc" } else { "asc" })).on_press(Message::SortOrderToggled).style(theme));
+        list_settings_row = list_settings_row.push(PickList::new(&mut self.coin_filter_picklist_state, coin_filters, Some(Filter::from_option(list_options.coin.clone())), Message::CoinFilterPicked).width(Length::FillPortion(2)).style(theme));
+        list_settings_row = list_settings_row.push(PickList::new(&mut self.currency_filter_picklist_state, currency_filters, Some(Filter::from_option(list_options.currency.clone())), Message::CurrencyFilterPicked).width(Length::FillPortion(1)).style(theme));
+        list_settings_row = list_settings_row.push(PickList::new(&mut self.direction_filter_picklist_state, direction_filters, Some(Filter::from_option(list_options.direction)), Message::DirectionFilterPicked).width(Length::FillPortion(1)).style(theme));
+        list_settings_row = list_settings_row.push(PickList::new(&mut self.state_filter_picklist_state, state_filters, Some(Filter::from_option(list_options.state)), Message::StateFilterPicked).width(Length::FillPortion(1)).style(theme));
+        list_settings_row = list_settings_row.push(TextInput::new(&mut self.search_input_state, "Search", &mut list_options.search, Message::SearchChanged).on_submit(Message::SearchSubmitted).width(Length::FillPortion(2)).padding(5).style(theme));
+
+        main_column = main_column.push(list_settings_row);
+
+        if let Some((ref group, ref rowids)) = self.pending_group_delete {
+            let mut confirm_row = Row::new()
+                .spacing(5)
+                .width(Length::Fill)
+                .height(Length::Shrink);
+            let group = group.clone().unwrap_or_else(|| "Ungrouped".to_string());
+            confirm_row = confirm_row.push(Text::new(format!("Delete {} trigger(s) of {}? This cannot be undone.", rowids.len(), group)).size(16));
+            confirm_row = confirm_row.push(Button::new(&mut self.confirm_group_delete_button_state, Text::new("Delete")).on_press(Message::ConfirmGroupDeleteClicked).style(theme));
+            confirm_row = confirm_row.push(Button::new(&mut self.cancel_group_delete_button_state, Text::new("Cancel")).on_press(Message::CancelGroupDeleteClicked).style(theme));
+            main_column = main_column.push(confirm_row);
+        }
+
+        for err in self.store_error.iter().chain(self.settings_error.iter()) {
+            main_column = main_column.push(Text::new(err.clone()).size(16).color(Color::from_rgb8(0xD0, 0x3B, 0x3B)));
+        }
+
+        items.retain(|(_, _, _, item)| list_options.matches(item));
+        // Named groups come first, in alphabetical order, and the list options order the triggers within each group.
+        items.sort_by(|(_, _, _, left), (_, _, _, right)| {
+            (left.group.is_none(), &left.group).cmp(&(right.group.is_none(), &right.group))
+                .then(list_options.compare(left, right))
+        });
+
+        let mut groups: Vec<(Option<String>, Vec<i64>)> = Vec::new();
+        for (trigger, _, _, item) in items.iter() {
+            match groups.last_mut() {
+                Some((group, rowids)) if *group == item.group => rowids.push(trigger.rowid),
+                _ => groups.push((item.group.clone(), vec![trigger.rowid])),
+            }
+        }
+
+        self.delete_button_states = vec![Default::default(); items.len()];
+        self.snooze_button_states = vec![Default::default(); items.len()];
+        self.group_button_states = vec![Default::default(); groups.len()];
+        let mut group_headers = groups.into_iter().zip(self.group_button_states.iter_mut());
+        let mut current_group = None;
+
+        let mut scrollable = Scrollable::new(&mut self.scrollable_state)
+            .width(Length::Fill)
+            .height(Length::Fill);
+
+        if items.is_empty() && !self.triggers.is_empty() {
+            scrollable = scrollable.push(Text::new("No triggers match the current filters."));
+        }
+
+        for (((trigger, current_price, file_key, item), delete_button_state), snooze_button_state) in items.into_iter().zip(self.delete_button_states.iter_mut()).zip(self.snooze_button_states.iter_mut()) {
             let initial_price = trigger.initial_price;
             let target_price = trigger.target_price;
+            let near_target = item.distance.map(|distance| distance.abs() <= NEAR_TARGET_PERCENT).unwrap_or(false);
+
+            if current_group.as_ref() != Some(&item.group) {
+                let ((group, rowids), (toggle_button_state, action_button_states)) = group_headers.next().unwrap();
+                let collapsed = self.collapsed_groups.contains(&group);
+                let mut group_header_row = Row::new().padding(5).spacing(5).width(Length::Fill);
+                group_header_row = group_header_row.push(Button::new(toggle_button_state, Text::new(if collapsed { "+" } else { "-" })).on_press(Message::GroupToggled(group.clone())).style(theme));
+                group_header_row = group_header_row.push(Text::new(format!("{} ({})", group.clone().unwrap_or_else(|| "Ungrouped".to_string()), rowids.len())).width(Length::Fill));
+                for (action, action_button_state) in GroupAction::ALL.iter().zip(action_button_states.iter_mut()) {
+                    group_header_row = group_header_row.push(Button::new(action_button_state, Text::new(action.to_string())).on_press(Message::GroupActionClicked(group.clone(), *action, rowids.clone())).style(theme));
+                }
+                scrollable = scrollable.push(group_header_row);
+                current_group = Some(group);
+            }
+            if self.collapsed_groups.contains(&item.group) {
+                continue;
+            }
+
             let mut trigger_row = Row::new().padding(5).spacing(5).width(Length::Fill);
-            trigger_row = trigger_row.push(Button::new(delete_button_states.pop().unwrap(), Text::new("delete")).on_press(Message::DeleteTriggerClicked(trigger.rowid)).style(theme));
-            trigger_row = trigger_row.push(Text::new(format!("Trigger #{}: coin: {}, currency: {} from {} to {}", trigger.rowid, coin.raw.id, currency.raw.name, initial_price, target_price)));
+            // Triggers owned by the triggers file can only be changed by editing the file.
+            if file_key.is_none() {
+                trigger_row = trigger_row.push(Button::new(delete_button_state, Text::new("delete")).on_press(Message::DeleteTriggerClicked(trigger.rowid)).style(theme));
+                trigger_row = match item.state {
+                    TriggerState::Active => trigger_row.push(Button::new(snooze_button_state, Text::new("snooze")).on_press(Message::SnoozeClicked(trigger.rowid)).style(theme)),
+                    TriggerState::Snoozed => trigger_row.push(Button::new(snooze_button_state, Text::new("wake")).on_press(Message::WakeClicked(trigger.rowid)).style(theme)),
+                    TriggerState::Expired => trigger_row,
+                    TriggerState::Disabled => trigger_row.push(Button::new(snooze_button_state, Text::new("enable")).on_press(Message::EnableClicked(trigger.rowid)).style(theme)),
+                };
+            }
+
+            let mut trigger_column = Column::new().spacing(2).width(Length::Fill);
+            let mut trigger_text = Text::new(format!("Trigger #{}: coin: {}, currency: {} from {} to {} ({})", trigger.rowid, item.coin, item.currency, initial_price, target_price, item.state));
+            if self.focused_trigger == Some(trigger.rowid) {
+                trigger_text = trigger_text.color(Color::from_rgb8(0xE0, 0x8E, 0x0B));
+            }
+            trigger_column = trigger_column.push(trigger_text);
+            if !item.labels.is_empty() {
+                let mut labels_row = Row::new().spacing(8);
+                for label in item.labels.iter() {
+                    labels_row = labels_row.push(Text::new(label.clone()).size(14).color(crate::styling::label_color(label)));
+                }
+                trigger_column = trigger_column.push(labels_row);
+            }
+            if let Some(ref note) = item.note {
+                trigger_column = trigger_column.push(Text::new(note.clone()).size(14));
+            }
+            if let Some(file_key) = file_key {
+                trigger_column = trigger_column.push(Text::new(format!("read-only, from the triggers file entry '{}'", file_key)).size(14));
+            }
+
+            let mut price_row = Row::new().spacing(5).width(Length::Fill);
+            match current_price.zip(item.distance) {
+                Some((current_price, distance)) => {
+                    // How much of the way from the initial price to the target has been covered.
+                    let progress = ((current_price - initial_price) / (target_price - initial_price)).max(0.0).min(1.0);
+                    let mut price_text = Text::new(format!("now: {}, {:+.2}% to target", current_price, distance)).size(16);
+                    if near_target {
+                        price_text = price_text.color(Color::from_rgb8(0xD0, 0x3B, 0x3B));
+                    }
+                    price_row = price_row.push(ProgressBar::new(0.0..=1.0, progress as f32).width(Length::Units(200)).height(Length::Units(12)).style(theme));
+                    price_row = price_row.push(price_text);
+                }
+                None => {
+                    price_row = price_row.push(Text::new("now: waiting for the next trigger check").size(16));
+                }
+            }
+            trigger_column = trigger_column.push(price_row);
+
+            trigger_row = trigger_row.push(trigger_column);
             scrollable = scrollable.push(trigger_row);
         }
 
@@ -163,30 +821,70 @@ impl Gui {
     }
 }
 
-pub async fn update_triggers() -> Result<Vec<coingecko_requests::data::Trigger>, Box<dyn std::error::Error>> {
-    let api_client = coingecko_requests::api_client::Client::new();
-    let client = coingecko_requests::caching_client::Client::new(api_client).await?;
+/// Parses a target price typed on the form. A target that can be saved comes with a warning
+/// when it looks like a mistake compared to `current_price`.
+pub fn check_target(input: &str, current_price: Option<f64>) -> Result<(f64, Option<String>), String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(format!("Enter a target price"));
+    }
+    let target = input.parse::<f64>().map_err(|_| format!("'{}' is not a number", input))?;
+    if !target.is_finite() || target <= 0.0 {
+        return Err(format!("The target price must be positive"));
+    }
+    let warning = current_price.and_then(|price| {
+        let distance = (target - price) / price * 100.0;
+        if target == price {
+            Some(format!("The target is the current price, the trigger will fire right away"))
+        } else if distance.abs() > IMPLAUSIBLE_MOVE_PERCENT {
+            Some(format!("The target is {:.0}% away from the current price", distance.abs()))
+        } else {
+            None
+        }
+    });
+    Ok((target, warning))
+}
+
+pub fn reload_triggers() -> Command<Message> {
+    Command::perform(trigger_store::load(), |result| Message::TriggersUpdated(result.map_err(|err| err.to_string())))
+}
 
-    Ok(client.get_all_triggers().await?)
+fn update_meta<F: FnMut(&mut TriggerMeta) + Send + 'static>(rowids: Vec<i64>, f: F) -> Command<Message> {
+    Command::perform(trigger_store::update_meta(rowids, f), |result| Message::TriggerMetaUpdated(result.map_err(|err| err.to_string())))
 }
 
-pub async fn add_trigger(coin: Coin, currency: VsCurrency, value: f64) -> Result<(), Box<dyn std::error::Error>> {
-    let api_client = coingecko_requests::api_client::Client::new();
-    let client = coingecko_requests::caching_client::Client::new(api_client).await?;
+fn delete_triggers(rowids: Vec<i64>) -> Command<Message> {
+    Command::perform(trigger_store::delete(rowids), |result| Message::TriggerDeleted(result.map_err(|err| err.to_string())))
+}
 
-    let data = client.price(&[coin.raw.id.as_str()], &[currency.raw.name.as_str()]).await?;
+fn fetch_live_price(coin: &Coin, currency: &VsCurrency) -> Command<Message> {
+    let (coin_id, currency_id) = (coin.rowid, currency.rowid);
+    Command::perform(trigger_store::current_price(coin.raw.id.clone(), currency.raw.name.clone()), move |result| Message::LivePriceFetched(coin_id, currency_id, result.map_err(|err| err.to_string())))
+}
 
-    client.add_trigger(coin.rowid, currency.rowid, data[&coin.raw.id][&currency.raw.name], value).await?;
+pub async fn export_triggers(path: PathBuf, records: Vec<TriggerRecord>, format: TransferFormat) -> Result<(PathBuf, usize), Box<dyn std::error::Error>> {
+    let content = trigger_io::write(&records, format)?;
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    tokio::fs::write(&path, content).await?;
 
-    Ok(())
+    Ok((path, records.len()))
 }
 
-pub async fn delete_trigger(trigger_id: i64) -> Result<(), Box<dyn std::error::Error>> {
-    let api_client = coingecko_requests::api_client::Client::new();
-    let client = coingecko_requests::caching_client::Client::new(api_client).await?;
+pub async fn read_triggers(path: PathBuf) -> Result<Vec<TriggerRecord>, Box<dyn std::error::Error>> {
+    let format = TransferFormat::from_path(&path)
+        .ok_or::<Box<dyn std::error::Error>>(From::from("Only .csv and .json files can be imported!"))?;
+    let content = tokio::fs::read_to_string(&path).await?;
 
-    client.delete_trigger(trigger_id).await?;
+    trigger_io::read(&content, format)
+}
 
-    Ok(())
+pub async fn import_triggers(records: Vec<(Coin, VsCurrency, TriggerRecord)>) -> Result<usize, Box<dyn std::error::Error>> {
+    let count = records.len();
+    for (coin, currency, record) in records {
+        trigger_store::add(coin, currency, record.initial_price, record.target_price, record.meta()).await?;
+    }
 
-}
\ No newline at end of file
+    Ok(count)
+}